@@ -1,4 +1,5 @@
 // config.rs
+use serde::{Deserialize, Serialize};
 
 // game state
 #[derive(PartialEq)]
@@ -17,6 +18,7 @@ impl GameState {
 }
 
 // fly mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum FlyMode {
     Wander,
     Reborn,
@@ -30,7 +32,7 @@ pub enum Popup {
     Exit,
 }
 /// WorldConfig: Game world configuration parameters
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WorldConfig {
     /// Window width
     pub width: f32,
@@ -70,10 +72,86 @@ impl IconType {
     }
 }
 
+// symbols of the pop-ups, name and text, the fonts of every locale must have them
+pub const ICON_SYMBOLS: &[(&str, &str)] = &[
+    ("icon.error", "❌"),
+    ("icon.warning", "❗"),
+    ("icon.info", "🔔"),
+    ("icon.trophy", "🏆"),
+];
+
 // difficulty
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Difficulty {
     Easy,
     Medium,
     Hard,
 }
+impl Difficulty {
+    /// Name of the difficulty, as stored with the scores
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Stars of the difficulty
+    pub fn stars(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "⭐",
+            Difficulty::Medium => "⭐⭐",
+            Difficulty::Hard => "⭐⭐⭐",
+        }
+    }
+
+    /// How hard the fly runs away from the cursor, 0.0..=1.0
+    pub fn evasion_strength(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.3,
+            Difficulty::Medium => 0.6,
+            Difficulty::Hard => 1.0,
+        }
+    }
+}
+
+// game mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Flies run away from the cursor
+    #[default]
+    Classic,
+}
+impl GameMode {
+    /// Name of the mode, as stored with the scores
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+        }
+    }
+}
+
+/// EvasionConfig: how the fly runs away from the cursor
+#[derive(Clone, Copy)]
+pub struct EvasionConfig {
+    /// The fly feels the cursor inside this distance
+    pub radius: f32,
+    /// Acceleration away from the cursor per tick at full strength
+    pub acceleration: f32,
+    /// Max evasion speed per tick
+    pub max_speed: f32,
+    /// Evasion strength, 0.0 turns evasion off
+    pub strength: f32,
+}
+
+impl Default for EvasionConfig {
+    fn default() -> Self {
+        Self {
+            radius: 150.0,
+            acceleration: 12.0,
+            max_speed: 40.0,
+            strength: Difficulty::Easy.evasion_strength(),
+        }
+    }
+}
@@ -0,0 +1,365 @@
+// fly behaviour
+use crate::config::{Difficulty, EvasionConfig, WorldConfig};
+use rand::{Rng, RngCore};
+use std::f32::consts::TAU;
+
+/// MoveContext: what a behaviour may read while moving a fly
+pub struct MoveContext<'a> {
+    /// World configuration
+    pub config: &'a WorldConfig,
+    /// Speed factor of the fly on this tick
+    pub speed: f32,
+    /// Pointer position in fly coordinates, None when outside the window
+    pub pointer: Option<(f32, f32)>,
+    /// How the fly runs away from the pointer
+    pub evasion: &'a EvasionConfig,
+    /// Random source, the only randomness a behaviour may use
+    pub rng: &'a mut dyn RngCore,
+}
+
+impl MoveContext<'_> {
+    /// Allowed area of the fly position: (min_x, max_x, min_y, max_y)
+    pub fn bounds(&self) -> (f32, f32, f32, f32) {
+        let margin = self.config.margin;
+        (
+            margin,
+            self.config.width - margin,
+            margin,
+            self.config.height - margin,
+        )
+    }
+
+    /// Clamp the position into the allowed area
+    pub fn clamp(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (min_x, max_x, min_y, max_y) = self.bounds();
+        (x.clamp(min_x, max_x), y.clamp(min_y, max_y))
+    }
+
+    /// A random position inside the allowed area
+    pub fn random_position(&mut self) -> (f32, f32) {
+        let (min_x, max_x, min_y, max_y) = self.bounds();
+        let x = min_x + self.rng.random_range(0.0..1.0) * (max_x - min_x);
+        let y = min_y + self.rng.random_range(0.0..1.0) * (max_y - min_y);
+        (x, y)
+    }
+
+    /// A random unit direction
+    pub fn random_direction(&mut self) -> (f32, f32) {
+        let angle = self.rng.random_range(0.0..TAU);
+        (angle.cos(), angle.sin())
+    }
+}
+
+/// FlyBehaviour: how a fly moves on each tick
+///
+/// Implement it to add a new movement pattern without touching the model.
+///
+/// # Examples
+/// ```
+/// use engin::fly_behaviour::{FlyBehaviour, MoveContext};
+///
+/// /// Walk to the right until the wall
+/// struct Crab;
+///
+/// impl FlyBehaviour for Crab {
+///     fn name(&self) -> &'static str {
+///         "crab"
+///     }
+///
+///     fn next_position(&mut self, (x, y): (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+///         ctx.clamp((x + ctx.speed, y))
+///     }
+/// }
+/// ```
+pub trait FlyBehaviour {
+    /// Name of the behaviour
+    fn name(&self) -> &'static str;
+
+    /// Next position of the fly from its current position
+    fn next_position(&mut self, pos: (f32, f32), ctx: &mut MoveContext) -> (f32, f32);
+}
+
+/// Create a new behaviour
+pub type BehaviourFactory = fn() -> Box<dyn FlyBehaviour>;
+
+/// Behaviours a new fly can get on each difficulty
+pub fn behaviour_pool(difficulty: Difficulty) -> &'static [BehaviourFactory] {
+    match difficulty {
+        Difficulty::Easy => &[RandomWalk::boxed],
+        Difficulty::Medium => &[RandomWalk::boxed, ZigZag::boxed, Orbit::boxed],
+        Difficulty::Hard => &[
+            RandomWalk::boxed,
+            ZigZag::boxed,
+            Orbit::boxed,
+            Dash::boxed,
+            BezierGlide::boxed,
+        ],
+    }
+}
+
+/// RandomWalk: wander a random step on each tick
+#[derive(Default)]
+pub struct RandomWalk;
+
+impl RandomWalk {
+    /// A new RandomWalk, boxed for the behaviour pool
+    pub fn boxed() -> Box<dyn FlyBehaviour> {
+        Box::new(Self)
+    }
+}
+
+impl FlyBehaviour for RandomWalk {
+    fn name(&self) -> &'static str {
+        "random_walk"
+    }
+
+    fn next_position(&mut self, (x, y): (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+        let x_rand = ctx.rng.random_range(-1.0..1.0);
+        let y_rand = ctx.rng.random_range(-1.0..1.0);
+        ctx.clamp((x + x_rand * ctx.speed, y + y_rand * ctx.speed))
+    }
+}
+
+/// Dash: run in a straight line and bounce on the walls
+#[derive(Default)]
+pub struct Dash {
+    direction: Option<(f32, f32)>, // unit direction, random on first move
+}
+
+impl Dash {
+    /// A new Dash, boxed for the behaviour pool
+    pub fn boxed() -> Box<dyn FlyBehaviour> {
+        Box::new(Self::default())
+    }
+}
+
+impl FlyBehaviour for Dash {
+    fn name(&self) -> &'static str {
+        "dash"
+    }
+
+    fn next_position(&mut self, (x, y): (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+        let (mut dx, mut dy) = match self.direction {
+            Some(direction) => direction,
+            None => ctx.random_direction(),
+        };
+        let (min_x, max_x, min_y, max_y) = ctx.bounds();
+        let x_new = x + dx * ctx.speed;
+        let y_new = y + dy * ctx.speed;
+        // bounce on the walls
+        if x_new < min_x || x_new > max_x {
+            dx = -dx;
+        }
+        if y_new < min_y || y_new > max_y {
+            dy = -dy;
+        }
+        self.direction = Some((dx, dy));
+        ctx.clamp((x_new, y_new))
+    }
+}
+
+/// BezierGlide: glide along quadratic Bezier curves between random waypoints
+#[derive(Default)]
+pub struct BezierGlide {
+    curve: Option<[(f32, f32); 3]>, // start, control, end
+    t: f32,                         // progress on the curve, 0.0..=1.0
+}
+
+impl BezierGlide {
+    /// A new BezierGlide, boxed for the behaviour pool
+    pub fn boxed() -> Box<dyn FlyBehaviour> {
+        Box::new(Self::default())
+    }
+
+    // point on the curve at t
+    fn point([p0, p1, p2]: [(f32, f32); 3], t: f32) -> (f32, f32) {
+        let u = 1.0 - t;
+        (
+            u * u * p0.0 + 2.0 * u * t * p1.0 + t * t * p2.0,
+            u * u * p0.1 + 2.0 * u * t * p1.1 + t * t * p2.1,
+        )
+    }
+}
+
+impl FlyBehaviour for BezierGlide {
+    fn name(&self) -> &'static str {
+        "bezier_glide"
+    }
+
+    fn next_position(&mut self, pos: (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+        let curve = match self.curve {
+            Some(curve) if self.t < 1.0 => curve,
+            _ => {
+                // next waypoint from where the fly is
+                let curve = [pos, ctx.random_position(), ctx.random_position()];
+                self.curve = Some(curve);
+                self.t = 0.0;
+                curve
+            }
+        };
+        // chord length is close enough to keep the glide speed
+        let [p0, _, p2] = curve;
+        let length = ((p2.0 - p0.0).powi(2) + (p2.1 - p0.1).powi(2))
+            .sqrt()
+            .max(1.0);
+        self.t = (self.t + ctx.speed / length).min(1.0);
+        ctx.clamp(Self::point(curve, self.t))
+    }
+}
+
+/// Orbit: circle around a center near the fly
+#[derive(Default)]
+pub struct Orbit {
+    orbit: Option<((f32, f32), f32)>, // center, radius
+    angle: f32,                       // angle on the orbit
+}
+
+impl Orbit {
+    /// A new Orbit, boxed for the behaviour pool
+    pub fn boxed() -> Box<dyn FlyBehaviour> {
+        Box::new(Self::default())
+    }
+}
+
+impl FlyBehaviour for Orbit {
+    fn name(&self) -> &'static str {
+        "orbit"
+    }
+
+    fn next_position(&mut self, (x, y): (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+        let (center, radius) = match self.orbit {
+            Some(orbit) => orbit,
+            None => {
+                // the fly starts on the orbit
+                let radius = ctx.rng.random_range(40.0..120.0);
+                self.angle = ctx.rng.random_range(0.0..TAU);
+                let center = (x - radius * self.angle.cos(), y - radius * self.angle.sin());
+                self.orbit = Some((center, radius));
+                (center, radius)
+            }
+        };
+        self.angle = (self.angle + ctx.speed / radius) % TAU;
+        ctx.clamp((
+            center.0 + radius * self.angle.cos(),
+            center.1 + radius * self.angle.sin(),
+        ))
+    }
+}
+
+/// ZigZag: move forward while swinging left and right
+#[derive(Default)]
+pub struct ZigZag {
+    heading: Option<(f32, f32)>, // unit direction, random on first move
+    step: u32,                   // steps on the current leg
+    side: f32,                   // swing side, 1.0 or -1.0
+}
+
+impl ZigZag {
+    /// Steps of a leg before swinging to the other side
+    const LEG: u32 = 4;
+
+    /// A new ZigZag, boxed for the behaviour pool
+    pub fn boxed() -> Box<dyn FlyBehaviour> {
+        Box::new(Self::default())
+    }
+}
+
+impl FlyBehaviour for ZigZag {
+    fn name(&self) -> &'static str {
+        "zig_zag"
+    }
+
+    fn next_position(&mut self, (x, y): (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+        let (mut hx, mut hy) = match self.heading {
+            Some(heading) => heading,
+            None => {
+                self.side = 1.0;
+                ctx.random_direction()
+            }
+        };
+        // swing to the other side at the end of a leg
+        self.step += 1;
+        if self.step >= Self::LEG {
+            self.step = 0;
+            self.side = -self.side;
+        }
+        // forward plus sideways on the perpendicular
+        let (px, py) = (-hy * self.side, hx * self.side);
+        let x_new = x + (hx + px) * ctx.speed * 0.5;
+        let y_new = y + (hy + py) * ctx.speed * 0.5;
+        // turn back on the walls
+        let (min_x, max_x, min_y, max_y) = ctx.bounds();
+        if x_new < min_x || x_new > max_x {
+            hx = -hx;
+        }
+        if y_new < min_y || y_new > max_y {
+            hy = -hy;
+        }
+        self.heading = Some((hx, hy));
+        ctx.clamp((x_new, y_new))
+    }
+}
+
+/// Evade: run away from the cursor on top of another behaviour
+///
+/// The fly accelerates away when the cursor comes inside the evasion
+/// radius, and slows down again when it is left alone. The name is the
+/// name of the inner behaviour.
+pub struct Evade {
+    inner: Box<dyn FlyBehaviour>,
+    velocity: (f32, f32), // evasion velocity per tick
+}
+
+impl Evade {
+    /// Slow down of the evasion velocity per tick
+    const DRAG: f32 = 0.8;
+
+    /// Evade the cursor on top of the inner behaviour
+    pub fn new(inner: Box<dyn FlyBehaviour>) -> Self {
+        Self {
+            inner,
+            velocity: (0.0, 0.0),
+        }
+    }
+}
+
+impl FlyBehaviour for Evade {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn next_position(&mut self, pos: (f32, f32), ctx: &mut MoveContext) -> (f32, f32) {
+        let (x, y) = self.inner.next_position(pos, ctx);
+        let evasion = *ctx.evasion;
+        let (mut vx, mut vy) = (self.velocity.0 * Self::DRAG, self.velocity.1 * Self::DRAG);
+
+        if let Some((px, py)) = ctx.pointer
+            && evasion.strength > 0.0
+        {
+            let (dx, dy) = (x - px, y - py);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance < evasion.radius {
+                // closer cursor, harder push
+                let (ux, uy) = if distance > f32::EPSILON {
+                    (dx / distance, dy / distance)
+                } else {
+                    ctx.random_direction()
+                };
+                let push =
+                    evasion.acceleration * evasion.strength * (1.0 - distance / evasion.radius);
+                vx += ux * push;
+                vy += uy * push;
+            }
+        }
+
+        // limit the evasion speed
+        let speed = (vx * vx + vy * vy).sqrt();
+        let max_speed = evasion.max_speed * evasion.strength;
+        if speed > max_speed && speed > 0.0 {
+            vx *= max_speed / speed;
+            vy *= max_speed / speed;
+        }
+        self.velocity = (vx, vy);
+        ctx.clamp((x + vx, y + vy))
+    }
+}
@@ -1,19 +1,107 @@
 // model
-use crate::config::{FlyMode, WorldConfig};
-use rand::{Rng, rng};
+use crate::config::{EvasionConfig, FlyMode, WorldConfig};
+use crate::fly_behaviour::{BehaviourFactory, Evade, FlyBehaviour, MoveContext, RandomWalk};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+/// Fly: one Rustacean on the beach
+pub struct Fly {
+    pub id: u32,                      // fly id, unique in a game
+    pub x: f32,                       // fly at x
+    pub y: f32,                       // fly at y
+    pub mode: FlyMode,                // how the fly moves on tick
+    pub speed: f32,                   // speed factor relative to the difficulty speed
+    pub prev_x: f32,                  // fly at x on the previous tick
+    pub prev_y: f32,                  // fly at y on the previous tick
+    behaviour: Box<dyn FlyBehaviour>, // movement in FlyMode::Wander
+}
+
+impl Fly {
+    /// Create a fly at the position with the random walk behaviour
+    pub fn new(id: u32, x: f32, y: f32) -> Self {
+        Self {
+            id,
+            x,
+            y,
+            mode: FlyMode::Wander,
+            speed: 1.0,
+            prev_x: x,
+            prev_y: y,
+            behaviour: RandomWalk::boxed(),
+        }
+    }
+
+    /// Position to draw between the previous and the current tick
+    ///
+    /// `alpha` is the progress to the next tick, 0.0..=1.0.
+    pub fn lerp_position(&self, alpha: f32) -> (f32, f32) {
+        (
+            self.prev_x + (self.x - self.prev_x) * alpha,
+            self.prev_y + (self.y - self.prev_y) * alpha,
+        )
+    }
+
+    /// Stay at the current position, without motion to draw
+    pub fn settle(&mut self) {
+        (self.prev_x, self.prev_y) = (self.x, self.y);
+    }
+
+    /// Name of the current movement behaviour
+    pub fn behaviour_name(&self) -> &'static str {
+        self.behaviour.name()
+    }
+
+    /// Switch to another movement behaviour, also in the middle of a round
+    pub fn set_behaviour(&mut self, behaviour: Box<dyn FlyBehaviour>) {
+        self.behaviour = behaviour;
+    }
+}
 
 /// FlyModel: Encapsulates fly position logic
+///
+/// All randomness comes from the owned `rng`, so the same seed plus the
+/// same inputs always produce the same fly trajectory.
 pub struct FlyModel {
-    pub config: WorldConfig, // config setting
-    pub fly_speed: f32,      // fly speed factor
-    pub fly_hunted: u32,     // fly hunting number
-    pub fly_time: f32,       // game total time in second
-    pub counter: f32,        // play time counter
+    pub config: WorldConfig,    // config setting
+    pub fly_speed: f32,         // fly speed factor
+    pub fly_hunted: u32,        // fly hunting number
+    pub fly_time: f32,          // game total time in second
+    pub counter: f32,           // play time counter
+    pub evasion: EvasionConfig, // how flies run away from the cursor
+    seed: Option<u64>,          // seed of rng, None when injected
+    rng: Box<dyn RngCore>,      // random source of the fly movement
 }
 
 impl FlyModel {
     /// Initialize FlyModel in the middle of window
+    ///
+    /// The random source is seeded from the OS entropy.
     pub fn new(config: WorldConfig) -> Self {
+        Self::with_seed(config, rand::random())
+    }
+
+    /// Initialize FlyModel with a fixed seed
+    ///
+    /// # Examples
+    /// ```
+    /// use engin::config::{FlyMode, WorldConfig};
+    /// use engin::fly_model::FlyModel;
+    ///
+    /// let mut a = FlyModel::with_seed(WorldConfig::default(), 7);
+    /// let mut b = FlyModel::with_seed(WorldConfig::default(), 7);
+    /// assert_eq!(
+    ///     a.fly_position(400.0, 300.0, FlyMode::Wander, 20.0),
+    ///     b.fly_position(400.0, 300.0, FlyMode::Wander, 20.0)
+    /// );
+    /// ```
+    pub fn with_seed(config: WorldConfig, seed: u64) -> Self {
+        let mut fly = Self::with_rng(config, Box::new(StdRng::seed_from_u64(seed)));
+        fly.seed = Some(seed);
+        fly
+    }
+
+    /// Initialize FlyModel with an injected random source
+    pub fn with_rng(config: WorldConfig, rng: Box<dyn RngCore>) -> Self {
         // Return the struct directly
         Self {
             config, // This uses "Field Init Shorthand" since name and variable match
@@ -21,9 +109,28 @@ impl FlyModel {
             fly_hunted: 0,
             fly_time: 10.0,
             counter: 10.0, // init set to fly_time
+            evasion: EvasionConfig::default(),
+            seed: None,
+            rng,
         }
     }
 
+    /// The seed of the random source, `None` if the source was injected
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Draw a seed for a new round from the random source
+    pub fn next_seed(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    /// Restart the random source from the seed
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = Box::new(StdRng::seed_from_u64(seed));
+        self.seed = Some(seed);
+    }
+
     // reset the game
     pub fn fly_reset(&mut self) {
         self.counter = self.fly_time;
@@ -47,40 +154,84 @@ impl FlyModel {
         self.fly_hunted += 1;
     }
 
+    // spawn a new fly at a random position
+    // with a behaviour picked from the pool, evading the cursor
+    pub fn fly_spawn(&mut self, id: u32, pool: &[BehaviourFactory]) -> Fly {
+        let mut fly = Fly::new(id, self.config.width / 2.0, self.config.height / 2.0);
+        fly.speed = self.rng.random_range(0.8..1.2);
+        let behaviour = if pool.is_empty() {
+            RandomWalk::boxed()
+        } else {
+            let pick = self.rng.random_range(0..pool.len());
+            pool[pick]()
+        };
+        fly.set_behaviour(Box::new(Evade::new(behaviour)));
+        self.fly_reborn(&mut fly);
+        fly
+    }
+
+    // move the fly by its own mode, behaviour and speed
+    // pointer: the cursor the fly runs away from
+    pub fn fly_move(&mut self, fly: &mut Fly, pointer: Option<(f32, f32)>) {
+        fly.settle();
+        match fly.mode {
+            FlyMode::Wander => {
+                let mut ctx = MoveContext {
+                    config: &self.config,
+                    speed: self.fly_speed * fly.speed,
+                    pointer,
+                    evasion: &self.evasion,
+                    rng: &mut *self.rng,
+                };
+                (fly.x, fly.y) = fly.behaviour.next_position((fly.x, fly.y), &mut ctx);
+            }
+            FlyMode::Reborn => self.fly_reborn(fly),
+            FlyMode::Frozen => {}
+        }
+    }
+
+    // the fly hunted, move it to a new position
+    // the new position shows at once, without motion from the old one
+    pub fn fly_reborn(&mut self, fly: &mut Fly) {
+        (fly.x, fly.y) = self.fly_position(fly.x, fly.y, FlyMode::Reborn, 0.0);
+        fly.settle();
+    }
+
     // generate new fly position
-    pub fn fly_position(&mut self, fly_x: f32, fly_y: f32, mode: FlyMode) -> (f32, f32) {
+    pub fn fly_position(
+        &mut self,
+        fly_x: f32,
+        fly_y: f32,
+        mode: FlyMode,
+        speed_factor: f32,
+    ) -> (f32, f32) {
         let win_x = self.config.width;
         let win_y = self.config.height;
         let win_margin = self.config.margin;
-        let speed_factor = self.fly_speed;
-
-        let mut rand_num = rng();
-        let x_rand = rand_num.random_range(-1.0..1.0);
-        let y_rand = rand_num.random_range(-1.0..1.0);
-        let x_margin = win_x - win_margin * 2.0;
-        let y_margin = win_y - win_margin * 2.0;
-        let mut x_new = fly_x - win_margin;
-        let mut y_new = fly_y - win_margin;
-        // the fly hunted, generate a new position
+
         match mode {
             FlyMode::Wander => {
-                x_new = (x_new + x_rand * speed_factor).clamp(0.0, x_margin);
-                y_new = (y_new + y_rand * speed_factor).clamp(0.0, y_margin);
+                let mut ctx = MoveContext {
+                    config: &self.config,
+                    speed: speed_factor,
+                    pointer: None,
+                    evasion: &self.evasion,
+                    rng: &mut *self.rng,
+                };
+                RandomWalk.next_position((fly_x, fly_y), &mut ctx)
             }
             FlyMode::Reborn => {
-                x_new = x_rand.abs() * x_margin;
-                y_new = y_rand.abs() * y_margin;
-            }
-            _ => {
-                // freeze
-                x_new += 0.0;
-                y_new += 0.0;
+                // the fly hunted, generate a new position
+                let x_rand: f32 = self.rng.random_range(-1.0..1.0);
+                let y_rand: f32 = self.rng.random_range(-1.0..1.0);
+                let x_margin = win_x - win_margin * 2.0;
+                let y_margin = win_y - win_margin * 2.0;
+                (
+                    x_rand.abs() * x_margin + win_margin,
+                    y_rand.abs() * y_margin + win_margin,
+                )
             }
+            FlyMode::Frozen => (fly_x, fly_y),
         }
-        x_new += win_margin;
-        y_new += win_margin;
-
-        //return
-        (x_new, y_new)
     }
 }
@@ -2,79 +2,208 @@
 use chrono::Local;
 use eframe::egui::{self, Pos2, RichText};
 use egui::ViewportCommand;
-use std::sync::mpsc::{self, Receiver};
-use std::time::{Duration, Instant};
 
-use crate::config::{Difficulty, IconType, Popup, WorldConfig};
+use crate::config::{Difficulty, GameState, IconType, Popup, WorldConfig};
 use crate::fly_viewmodel::FlyViewModel;
-
-use util::assets::{I18NUIJSON, I18NUIZHCNJSON, I18NUIZHTWJSON, LanguageItem, MyAssets, UiConfig};
+use crate::overflow::OverflowDetector;
+use crate::replay::{KEPT_REPLAYS, Replay, ReplayPlayer};
+
+use util::assets::{LanguageItem, MyAssets, UiConfig};
+use util::customfonts::FontLibrary;
+use util::data_dir::export_dirs;
+use util::i18n::{DEFAULT_LOCALE, LocaleInfo, discover_locales};
+use util::message::Args;
+use util::pseudo::{PSEUDO_LOCALE, pseudo_language};
+use util::score::{BOARD_SIZE, Board, ScoreEntry, ScoreStore};
+use util::settings::Settings;
 use util::setup_custom_fonts;
-use util::{MyScore, get_resource_path_str};
+use util::skin::{Look, Skin, discover_skins, skins_dir};
+use util::{Error, MyScore, ScoreRepository, get_data_path};
+
+// seconds between retries of the score database
+const STORAGE_RETRY_SECS: f64 = 10.0;
+
+// rows of the leaderboard popup, read once while it is open
+struct Leaderboard {
+    board: Board,
+    top: std::result::Result<Vec<ScoreEntry>, String>,
+    player: String, // name the personal best is of
+    best: Option<ScoreEntry>,
+}
 
 // ---------- MyApp ----------
 pub struct MyApp {
     vm: FlyViewModel,
-    rx: Receiver<()>,
     assets: MyAssets,
     language: LanguageItem,
+    locales: Vec<LocaleInfo>, // locales found in the i18n directories
     ui_config: UiConfig,
+    ui_source: String, // where the labels came from
     now_time_display: String,
     dash_board_display: String,
     popup: Popup,
     check_exit: bool,
     should_exit: bool,
-    score: MyScore,
+    scores: ScoreStore,
+    retry_at: f64,                  // next time to retry the score database
+    storage_notice: Option<String>, // result of the last score export
+    player_name: String,
+    leaderboard: Option<Leaderboard>, // cached rows of the open popup
+    errors: Vec<String>,              // errors waiting for the error dialog
+    player: Option<ReplayPlayer>,     // replay playback mode
+    overflow: OverflowDetector,       // labels clipped by their rectangle
+    fonts: FontLibrary,               // font faces of the font manifest
+    fonts_locale: String,             // locale the fonts are set for
+    skins: Vec<Skin>,                 // skin packs of the skins directory
+    skin: Option<String>,             // id of the skin pack, the default look without one
+    look: Look,                       // sizes and colours of the skin
+    settings: Settings,               // choices kept between sessions
+    app_version: String,              // version of the game, kept with the scores
 }
 
 impl MyApp {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    /// Start the app, `app_version` is the version of the game kept with the scores
+    pub fn new(cc: &eframe::CreationContext<'_>, app_version: &str) -> Self {
+        // errors to show once the game is up
+        let mut errors = Vec::new();
+
         // set custom font
-        setup_custom_fonts(&cc.egui_ctx);
+        let fonts = setup_custom_fonts(&cc.egui_ctx, DEFAULT_LOCALE).unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            FontLibrary::default()
+        });
 
         // load image assets
-        let assets = MyAssets::load_from_json(&cc.egui_ctx);
+        let assets = MyAssets::load_from_json(&cc.egui_ctx).unwrap_or_else(|e| {
+            errors.push(e.to_string());
+            MyAssets::default()
+        });
 
         // load i18n
-        let path = &get_resource_path_str(I18NUIJSON);
-        let ui_config = UiConfig::load(path);
-        let language: LanguageItem = ui_config.languages[0].clone(); // default English // crash when package to macOS app
-
-        // timer thread
-        let (tx, rx) = mpsc::channel();
-        std::thread::spawn(move || {
-            // interval 0.1 seconds
-            let interval = Duration::from_secs_f64(0.1);
-            let mut next_tick = Instant::now();
-            loop {
-                // make sure the thread interval is fixed, not effected via inner program
-                next_tick += interval;
-                let _ = tx.send(());
-                // request repaint when wake up
-                let now = Instant::now();
-                if next_tick > now {
-                    std::thread::sleep(next_tick - now);
-                } else if now - next_tick > Duration::from_secs(1) {
-                    next_tick = now;
-                }
+        let (ui_config, ui_source) = match UiConfig::load_locale(DEFAULT_LOCALE) {
+            Ok((ui_config, source)) => (ui_config, source.to_string()),
+            Err(e) => {
+                errors.push(e.to_string());
+                (UiConfig::default(), String::from("built-in"))
             }
-        });
-
-        // score sqlite
-        let score = MyScore::new().expect("Failed to open db file");
+        };
+        let language: LanguageItem = ui_config.locale.clone(); // default English
+        let locales = discover_locales();
+
+        // score sqlite, the scores stay in the session without it
+        let db_path = MyScore::default_path();
+        if let Err(e) = MyScore::migrate_legacy(&db_path) {
+            errors.push(e.to_string());
+        }
+        let scores = ScoreStore::sqlite(db_path);
 
-        Self {
+        let mut app = Self {
             vm: FlyViewModel::new(WorldConfig::default()),
-            rx,
             assets,
             language,
+            locales,
             ui_config,
+            ui_source,
             now_time_display: String::from(" "),
             dash_board_display: String::from(" "),
             popup: Popup::None,
             check_exit: false,
             should_exit: false,
-            score,
+            scores,
+            retry_at: 0.0,
+            storage_notice: None,
+            player_name: "Guest".to_string(),
+            leaderboard: None,
+            errors,
+            player: None,
+            overflow: OverflowDetector::new(),
+            fonts,
+            fonts_locale: DEFAULT_LOCALE.to_string(),
+            skins: discover_skins(&skins_dir()),
+            skin: None,
+            look: Look::default(),
+            settings: Settings::load(&Settings::default_path()),
+            app_version: app_version.to_string(),
+        };
+
+        // the skin of the last session
+        if let Some(id) = app.settings.skin.clone()
+            && let Err(e) = app.apply_skin(&cc.egui_ctx, Some(&id))
+        {
+            log::warn!("skin {id}: {e}");
+        }
+        app
+    }
+
+    /// Start the app in playback mode of the replay
+    pub fn with_replay(
+        cc: &eframe::CreationContext<'_>,
+        app_version: &str,
+        replay: Replay,
+    ) -> Self {
+        let mut app = Self::new(cc, app_version);
+        let mut player = ReplayPlayer::new(replay);
+        app.vm = player.start();
+        app.player = Some(player);
+        app
+    }
+
+    /// Switch to the skin pack of the id, the default look without one, and
+    /// remember it for the next sessions
+    pub fn select_skin(&mut self, ctx: &egui::Context, id: Option<&str>) {
+        match self.apply_skin(ctx, id) {
+            Ok(()) => {
+                self.settings.skin = id.map(str::to_string);
+                if let Err(e) = self.settings.save(&Settings::default_path()) {
+                    log::warn!("{e}");
+                }
+            }
+            Err(e) => self.errors.push(e.to_string()),
+        }
+    }
+
+    // textures, fonts, sizes and colours of the skin over the default ones
+    fn apply_skin(&mut self, ctx: &egui::Context, id: Option<&str>) -> util::Result<()> {
+        let skin =
+            match id {
+                Some(id) => Some(self.skins.iter().find(|skin| skin.id == id).ok_or_else(
+                    || Error::Asset {
+                        path: skins_dir().join(id).display().to_string(),
+                        reason: "no such skin pack".to_string(),
+                    },
+                )?),
+                None => None,
+            };
+        let mut assets = MyAssets::load_from_json(ctx)?;
+        let mut fonts = Vec::new();
+        if let Some(skin) = skin {
+            assets.apply_skin(ctx, skin)?;
+            for file in &skin.fonts {
+                fonts.push((format!("{}: {file}", skin.path.display()), skin.read(file)?));
+            }
+        }
+        self.fonts.set_skin_fonts(&fonts)?;
+        // the fonts are set again on the next frame
+        self.fonts_locale.clear();
+        self.assets = assets;
+        self.look = skin.map(|skin| skin.look.clone()).unwrap_or_default();
+        // the flies keep the margin of the world from the border
+        self.look.fly_size = self.look.fly_size.min(self.vm.fly.config.margin);
+        self.skin = skin.map(|skin| skin.id.clone());
+        let text = self.look.text;
+        ctx.style_mut(|style| style.visuals.override_text_color = text);
+        Ok(())
+    }
+
+    /// Switch the labels to the locale of the code, the pseudo-locale too
+    pub fn select_locale(&mut self, code: &str) {
+        match UiConfig::load_locale(code) {
+            Ok((ui_config, source)) => {
+                self.language = ui_config.locale.clone();
+                self.ui_config = ui_config;
+                self.ui_source = source.to_string();
+            }
+            Err(e) => self.errors.push(e.to_string()),
         }
     }
 
@@ -87,20 +216,22 @@ impl MyApp {
         icon: IconType,
         ok_text: &str,
     ) {
+        self.refresh_leaderboard();
         egui::Window::new(title)
             .collapsible(false)
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
             .show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
-                    ui.add_sized(
-                        egui::vec2(480.0, 100.0),
-                        egui::Label::new(
-                            RichText::new(format!("{} {}", icon.to_emoji(), message))
-                                .size(28.0)
-                                .strong(),
-                        ),
+                    let text = format!("{} {}", icon.to_emoji(), message);
+                    let size = egui::vec2(480.0, 100.0);
+                    let label = ui.add_sized(
+                        size,
+                        egui::Label::new(RichText::new(&text).size(28.0).strong()),
                     );
+                    let font = egui::FontId::proportional(28.0);
+                    self.overflow
+                        .check_text(ctx, "popup message", &text, font, size.x, label.rect);
                     ui.add_space(12.0);
 
                     if ui
@@ -111,12 +242,24 @@ impl MyApp {
                         .clicked()
                     {
                         self.popup = Popup::None;
+                        self.leaderboard = None;
                         if self.check_exit {
                             self.should_exit = true;
                         }
-                        // add player and score
-                        self.score.score = self.vm.get_game_result_message() as i32;
-                        self.score.add();
+                        // add player and score to the board of the round
+                        let name = self.player_name.trim();
+                        if !self.check_exit && !name.is_empty() {
+                            let entry = ScoreEntry::new(
+                                name,
+                                self.vm.get_game_result_message() as i32,
+                                self.board(),
+                                self.vm.fly.fly_time,
+                                &self.app_version,
+                            );
+                            if let Err(e) = self.scores.insert(&entry) {
+                                self.errors.push(e.to_string());
+                            }
+                        }
                     }
                 });
                 ui.add_space(25.0);
@@ -127,20 +270,176 @@ impl MyApp {
                             egui::RichText::new(&self.ui_config.gameover.player).monospace(),
                         );
                         egui::Frame::NONE.show(ui, |ui| {
-                            ui.text_edit_singleline(&mut self.score.name)
+                            ui.text_edit_singleline(&mut self.player_name)
                                 .labelled_by(data_label.id)
                         });
+                        // personal best of the player on the board
+                        if let Some(best) = self.leaderboard.as_ref().and_then(|l| l.best.as_ref())
+                        {
+                            ui.label(RichText::new(format!("👑 {}", best.score)).monospace());
+                        }
                     });
                 }
                 // show players' score
                 ui.horizontal(|ui| {
                     // print list
-                    self.score.output.clear();
-                    self.score.output.push_str(&self.ui_config.gameover.ranking);
-                    self.score.list();
-                    ui.label(egui::RichText::new(&self.score.output).monospace());
+                    ui.label(egui::RichText::new(self.ranking_text()).monospace());
+                });
+            });
+    }
+
+    // ---------- storage warning ----------
+    // the scores are kept for the session only, retry the database now and
+    // then, and let the player export the scores meanwhile
+    fn show_storage_warning(&mut self, ctx: &egui::Context) {
+        if !self.scores.is_session_only() {
+            return;
+        }
+        let now = ctx.input(|i| i.time);
+        if now >= self.retry_at {
+            self.retry_at = now + STORAGE_RETRY_SECS;
+            if self.scores.retry().is_ok() {
+                self.leaderboard = None;
+                return;
+            }
+        }
+
+        let labels = &self.ui_config.storage;
+        let mut retry = false;
+        let mut export = false;
+        egui::Area::new("storage_warning".into())
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 4.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            RichText::new(format!(
+                                "{} {}",
+                                IconType::Warning.to_emoji(),
+                                labels.session_only
+                            ))
+                            .size(14.0),
+                        )
+                        .on_hover_text(self.scores.error().unwrap_or_default());
+                        retry = ui.button(format!("🔄 {}", labels.retry)).clicked();
+                        export = ui
+                            .add_enabled(
+                                !self.scores.pending().is_empty(),
+                                egui::Button::new(format!("💾 {}", labels.export)),
+                            )
+                            .clicked();
+                    });
+                    if let Some(notice) = &self.storage_notice {
+                        ui.label(RichText::new(notice).size(12.0));
+                    }
+                });
+            });
+
+        if retry {
+            self.leaderboard = None;
+            if let Err(e) = self.scores.retry() {
+                self.errors.push(e.to_string());
+            }
+        }
+        if export {
+            let name = Local::now()
+                .format("flyrust-scores-%Y%m%d-%H%M%S.json")
+                .to_string();
+            // the data directory is the one failing, export outside of it
+            match self.scores.export_to(&export_dirs(), &name) {
+                Ok((path, count)) => {
+                    let args = Args::new()
+                        .with("count", count)
+                        .with("path", path.display().to_string());
+                    self.storage_notice = Some(
+                        self.ui_config
+                            .format(&self.ui_config.storage.exported, &args),
+                    )
+                }
+                Err(e) => self.errors.push(e.to_string()),
+            }
+        }
+    }
+
+    // ---------- error dialog ----------
+    fn show_error_dialog(&mut self, ctx: &egui::Context) {
+        let Some(error) = self.errors.first() else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new(IconType::Error.to_emoji())
+            .id("error_dialog".into())
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 120.0))
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    ui.label(RichText::new(error).size(16.0));
+                    ui.add_space(8.0);
+                    close = ui
+                        .button(RichText::new(&self.ui_config.popup.ok).size(20.0))
+                        .clicked();
                 });
             });
+        if close {
+            self.errors.remove(0);
+        }
+    }
+
+    // board of the current round
+    fn board(&self) -> Board {
+        Board::new(self.vm.difficulty.name(), self.vm.mode.name())
+    }
+
+    // read the leaderboard of the popup again when its board or player changed
+    fn refresh_leaderboard(&mut self) {
+        let board = self.board();
+        let player = self.player_name.trim();
+        match &mut self.leaderboard {
+            Some(cached) if cached.board == board => {
+                if cached.player != player {
+                    cached.best = self.scores.personal_best(player, &board).ok().flatten();
+                    cached.player = player.to_string();
+                }
+            }
+            _ => {
+                self.leaderboard = Some(Leaderboard {
+                    top: self
+                        .scores
+                        .top(&board, BOARD_SIZE)
+                        .map_err(|e| e.to_string()),
+                    best: self.scores.personal_best(player, &board).ok().flatten(),
+                    player: player.to_string(),
+                    board,
+                });
+            }
+        }
+    }
+
+    // ranking list of the board of the current round
+    fn ranking_text(&self) -> String {
+        let mut output = format!(
+            "\n          {} {}",
+            self.vm.difficulty.stars(),
+            self.vm.mode.name()
+        );
+        output.push_str(&self.ui_config.gameover.ranking);
+        match self.leaderboard.as_ref().map(|l| &l.top) {
+            Some(Ok(entries)) => {
+                for (rank, entry) in entries.iter().enumerate() {
+                    output.push_str(&format!(
+                        "\n          {:^4}  {:>3}    {:^10}   {:^19}",
+                        rank + 1,
+                        entry.score,
+                        entry.name,
+                        entry.created_at
+                    ));
+                }
+            }
+            Some(Err(e)) => output.push_str(&format!("\n❗ DB error: {}", e)),
+            None => {}
+        }
+        output
     }
 
     // ---------- difficulty radio ----------
@@ -148,78 +447,154 @@ impl MyApp {
         egui::Area::new("difficulty_radio".into())
             .fixed_pos(Pos2::new(8.0, 570.0))
             .show(ctx, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label(RichText::new("🛠").size(20.0).strong());
+                // keep the difficulty of a round, the replay records it once
+                ui.add_enabled_ui(self.vm.state == GameState::Stopped, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("🛠").size(20.0).strong());
+
+                        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+                            let stars = difficulty.stars();
+                            let radio = ui.radio_value(
+                                &mut self.vm.difficulty,
+                                difficulty,
+                                RichText::new(stars).size(20.0).strong(),
+                            );
+                            // the text comes after the radio icon, inside the window
+                            let spacing = ui.spacing();
+                            let mut text_rect = radio.rect;
+                            text_rect.min.x += spacing.icon_width + spacing.icon_spacing;
+                            self.overflow.check_width(
+                                ctx,
+                                "difficulty radio",
+                                stars,
+                                egui::FontId::proportional(20.0),
+                                text_rect.intersect(ctx.content_rect()),
+                            );
+                        }
 
-                    ui.radio_value(
-                        &mut self.vm.difficulty,
-                        Difficulty::Easy,
-                        RichText::new("⭐").size(20.0).strong(),
-                    );
-                    ui.radio_value(
-                        &mut self.vm.difficulty,
-                        Difficulty::Medium,
-                        RichText::new("⭐⭐").size(20.0).strong(),
+                        self.vm.apply_difficulty();
+                    });
+                });
+            });
+    }
+
+    // ---------- replay bar ----------
+    fn show_replay_bar(&mut self, ctx: &egui::Context) {
+        let Some(player) = self.player.as_mut() else {
+            return;
+        };
+        let total = player.replay().ticks;
+        let mut tick = self.vm.tick_count();
+
+        egui::Area::new("replay_bar".into())
+            .fixed_pos(Pos2::new(8.0, 560.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("🎬").size(20.0));
+                    let slider = ui.add(
+                        egui::Slider::new(&mut tick, 0..=total)
+                            .show_value(false)
+                            .integer(),
                     );
-                    ui.radio_value(
-                        &mut self.vm.difficulty,
-                        Difficulty::Hard,
-                        RichText::new("⭐⭐⭐").size(20.0).strong(),
+                    ui.label(
+                        RichText::new(format!("{tick}/{total}"))
+                            .size(14.0)
+                            .monospace(),
                     );
-
-                    self.vm.apply_difficulty();
+                    if slider.changed() {
+                        player.seek(&mut self.vm, tick);
+                    }
                 });
             });
     }
 
     // ---------- draw fly ----------
     fn draw_rustacean(&mut self, ctx: &eframe::egui::Context) {
-        egui::Area::new("fly_rustacean".into())
-            .fixed_pos(egui::pos2(self.vm.fly_x, self.vm.fly_y))
-            .show(ctx, |ui| {
-                if let Some(tex) = self.assets.get("Rustacean") {
-                    let img = egui::Image::new(tex).fit_to_exact_size(egui::vec2(60.0, 60.0));
-
-                    if ui.add(egui::Button::image(img).frame(false)).clicked() {
-                        self.vm.fly_click();
+        let mut hunted: Option<u32> = None;
+        // smooth motion between ticks
+        let alpha = match &self.player {
+            Some(player) => player.interpolation(&self.vm),
+            None => self.vm.interpolation(),
+        };
+
+        for fly in &self.vm.flies {
+            let (x, y) = fly.lerp_position(alpha);
+            egui::Area::new(egui::Id::new(("fly_rustacean", fly.id)))
+                .fixed_pos(egui::pos2(x, y))
+                .show(ctx, |ui| {
+                    if let Some(tex) = self.assets.get("Rustacean") {
+                        let size = self.look.fly_size;
+                        let img = egui::Image::new(tex)
+                            .fit_to_exact_size(egui::vec2(size, size))
+                            .tint(self.look.fly);
+
+                        if ui.add(egui::Button::image(img).frame(false)).clicked() {
+                            hunted = Some(fly.id);
+                        }
                     }
-                }
-            });
+                });
+        }
+
+        // the replay does the clicks in playback
+        if let Some(id) = hunted
+            && self.player.is_none()
+        {
+            self.vm.fly_click(id);
+        }
     }
 
     // ---------- draw fly hammer ----------
     fn draw_fly_hand(&self, ctx: &eframe::egui::Context) {
-        if let Some(pos) = ctx.input(|i| i.pointer.hover_pos()) {
-            if let Some(tex) = self.assets.get("Hand") {
-                let painter = ctx.layer_painter(egui::LayerId::new(
-                    egui::Order::Foreground,
-                    egui::Id::new("hand_layer"),
-                ));
-
-                painter.image(
-                    tex.id(),
-                    egui::Rect::from_center_size(pos, egui::vec2(50.0, 50.0)),
-                    egui::Rect::from_min_max(egui::Pos2::ZERO, egui::Pos2::new(1.0, 1.0)),
-                    egui::Color32::WHITE,
-                );
-            }
+        // the recorded pointer in playback
+        let pointer = match &self.player {
+            Some(_) => self.vm.pointer().map(|(x, y)| {
+                let half = self.look.fly_size / 2.0;
+                Pos2::new(x + half, y + half)
+            }),
+            None => ctx.input(|i| i.pointer.hover_pos()),
+        };
+        if let Some(pos) = pointer
+            && let Some(tex) = self.assets.get("Hand")
+        {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("hand_layer"),
+            ));
+
+            painter.image(
+                tex.id(),
+                egui::Rect::from_center_size(
+                    pos,
+                    egui::vec2(self.look.hand_size, self.look.hand_size),
+                ),
+                egui::Rect::from_min_max(egui::Pos2::ZERO, egui::Pos2::new(1.0, 1.0)),
+                self.look.hand,
+            );
         }
     }
 
     fn language_selector(&mut self, ctx: &egui::Context) {
         let mut next_lang_code: Option<String> = None;
+        // the pseudo-locale is for layout testing, in debug builds or from the command line
+        let mut languages: Vec<LanguageItem> =
+            self.locales.iter().map(|l| l.language.clone()).collect();
+        if cfg!(debug_assertions) || self.language.code == PSEUDO_LOCALE {
+            languages.push(pseudo_language());
+        }
 
         egui::Area::new("language_selector".into())
             .fixed_pos(egui::Pos2::new(680.0, 560.0))
             .show(ctx, |ui| {
                 ui.horizontal(|ui| {
-                    ui.label(egui::RichText::new("🌐").size(20.0));
+                    // where the labels came from
+                    ui.label(egui::RichText::new("🌐").size(20.0))
+                        .on_hover_text(&self.ui_source);
 
-                    egui::ComboBox::from_id_salt("language_combo")
+                    let combo = egui::ComboBox::from_id_salt("language_combo")
                         .selected_text(egui::RichText::new(&self.language.name).size(14.0))
                         .width(80.0)
                         .show_ui(ui, |ui| {
-                            for lang in &self.ui_config.languages {
+                            for lang in &languages {
                                 if ui
                                     .selectable_value(
                                         &mut self.language,
@@ -232,18 +607,23 @@ impl MyApp {
                                 }
                             }
                         });
+                    // the combo keeps room for its arrow
+                    let spacing = ui.spacing();
+                    let mut text_rect = combo.response.rect;
+                    text_rect
+                        .set_width(text_rect.width() - spacing.icon_width - spacing.item_spacing.x);
+                    self.overflow.check_width(
+                        ctx,
+                        "language combo",
+                        &self.language.name,
+                        egui::FontId::proportional(14.0),
+                        text_rect,
+                    );
                 });
             });
 
         if let Some(code) = next_lang_code {
-            let path_lang = match code.as_str() {
-                "en" => I18NUIJSON,
-                "zh-TW" => I18NUIZHTWJSON,
-                "zh-CN" => I18NUIZHCNJSON,
-                _ => I18NUIJSON,
-            };
-            let path = &get_resource_path_str(path_lang);
-            self.ui_config = UiConfig::load(path);
+            self.select_locale(&code);
         }
 
         // update windows title
@@ -251,11 +631,90 @@ impl MyApp {
             self.ui_config.app_name.clone(),
         ));
     }
+
+    fn skin_selector(&mut self, ctx: &egui::Context) {
+        // the default look only, nothing to choose
+        if self.skins.is_empty() {
+            return;
+        }
+        let default_name = self.ui_config.labels.default_skin.clone();
+        let current = self
+            .skins
+            .iter()
+            .find(|skin| Some(&skin.id) == self.skin.as_ref())
+            .map_or(default_name.clone(), |skin| skin.name.clone());
+        let mut next_skin: Option<Option<String>> = None;
+
+        egui::Area::new("skin_selector".into())
+            .fixed_pos(egui::Pos2::new(540.0, 560.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("🎨").size(20.0))
+                        .on_hover_text(&self.ui_config.labels.skin);
+
+                    let choices = std::iter::once((None, default_name.as_str())).chain(
+                        self.skins
+                            .iter()
+                            .map(|skin| (Some(&skin.id), skin.name.as_str())),
+                    );
+                    let combo = egui::ComboBox::from_id_salt("skin_combo")
+                        .selected_text(egui::RichText::new(&current).size(14.0))
+                        .width(80.0)
+                        .show_ui(ui, |ui| {
+                            for (id, name) in choices {
+                                let selected = id == self.skin.as_ref();
+                                if ui
+                                    .selectable_label(
+                                        selected,
+                                        egui::RichText::new(name).size(14.0),
+                                    )
+                                    .clicked()
+                                    && !selected
+                                {
+                                    next_skin = Some(id.cloned());
+                                }
+                            }
+                        });
+                    // the combo keeps room for its arrow
+                    let spacing = ui.spacing();
+                    let mut text_rect = combo.response.rect;
+                    text_rect
+                        .set_width(text_rect.width() - spacing.icon_width - spacing.item_spacing.x);
+                    self.overflow.check_width(
+                        ctx,
+                        "skin combo",
+                        &current,
+                        egui::FontId::proportional(14.0),
+                        text_rect,
+                    );
+                });
+            });
+
+        if let Some(id) = next_skin {
+            self.select_skin(ctx, id.as_deref());
+        }
+    }
+
+    // ---------- save replay ----------
+    fn save_replay(&mut self) {
+        if let Some(replay) = self.vm.take_replay() {
+            let dir = get_data_path("replays");
+            if let Err(e) = replay.save_in(&dir, KEPT_REPLAYS) {
+                self.errors.push(format!("replay {}: {e}", dir.display()));
+            }
+        }
+    }
 }
 
 // ---------- App trait ----------
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.overflow.begin_frame();
+        // fonts of the locale, for a language switch
+        if self.fonts_locale != self.language.code {
+            self.fonts.apply(ctx, &self.language.code);
+            self.fonts_locale = self.language.code.clone();
+        }
         // check exit
         if self.check_exit {
             self.popup = Popup::Exit;
@@ -264,25 +723,43 @@ impl eframe::App for MyApp {
             ctx.send_viewport_cmd(ViewportCommand::Close);
         }
 
-        // timer tick procedure
-        if let Ok(_) = self.rx.try_recv() {
-            if self.vm.tick() {
-                self.popup = Popup::GameOver;
+        let ticks = match self.player.as_mut() {
+            // replay playback procedure
+            Some(player) => {
+                player.update(&mut self.vm);
+                1
             }
+            None => {
+                // the fly watches the cursor, from the center of the fly
+                let pointer = ctx.input(|i| i.pointer.hover_pos());
+                let fly_size = self.look.fly_size;
+                self.vm.set_pointer(
+                    pointer.map(|pos| (pos.x - fly_size / 2.0, pos.y - fly_size / 2.0)),
+                );
 
+                // timer tick procedure
+                let (ticks, game_over) = self.vm.update();
+                if game_over {
+                    self.popup = Popup::GameOver;
+                    self.save_replay();
+                }
+                ticks
+            }
+        };
+        if ticks > 0 {
             // update time
             let now_time = Local::now();
             self.now_time_display = now_time.format("%Y-%m-%d %H:%M:%S\n").to_string();
 
             // update dash board
-            let (str_sec, str_score) = self.vm.dash_board_info();
+            let (str_sec, _) = self.vm.dash_board_info();
+            let args = Args::new()
+                .with("time", str_sec)
+                .with("score", self.vm.get_game_result_message());
 
             let str_disp = self
                 .ui_config
-                .labels
-                .dashboard
-                .replace("{time}", &str_sec)
-                .replace("{score}", &str_score);
+                .format(&self.ui_config.labels.dashboard, &args);
 
             self.dash_board_display = str_disp;
             // request repaint per tick
@@ -294,61 +771,117 @@ impl eframe::App for MyApp {
             .frame(egui::Frame::NONE.inner_margin(0.0))
             .show(ctx, |ui| {
                 if let Some(tex) = self.assets.get("Beach") {
-                    ui.add(egui::Image::new(tex).fit_to_exact_size(ui.available_size()));
+                    ui.add(
+                        egui::Image::new(tex)
+                            .fit_to_exact_size(ui.available_size())
+                            .tint(self.look.background),
+                    );
                 }
 
                 let content_rect = ui.max_rect();
                 ui.scope_builder(egui::UiBuilder::new().max_rect(content_rect), |ui| {
-                    let mut draw_label = |pos: Pos2, size: f32, text: &str, width: f32| {
-                        let rect = egui::Rect::from_min_size(pos, egui::vec2(width, 50.0));
-                        ui.scope_builder(egui::UiBuilder::new().max_rect(rect), |ui| {
-                            ui.with_layout(
-                                egui::Layout::left_to_right(egui::Align::Center),
-                                |ui| ui.label(RichText::new(text).size(size).monospace()),
-                            );
-                        });
-                    };
+                    let overflow = &mut self.overflow;
+                    let mut draw_label =
+                        |widget: &str, pos: Pos2, size: f32, text: &str, width: f32| {
+                            let rect = egui::Rect::from_min_size(pos, egui::vec2(width, 50.0));
+                            // the window clips what the rectangle does not
+                            let visible = rect.intersect(content_rect);
+                            let font = egui::FontId::monospace(size);
+                            overflow.check_width(ui.ctx(), widget, text, font, visible);
+                            ui.scope_builder(egui::UiBuilder::new().max_rect(rect), |ui| {
+                                ui.with_layout(
+                                    egui::Layout::left_to_right(egui::Align::Center),
+                                    |ui| ui.label(RichText::new(text).size(size).monospace()),
+                                );
+                            });
+                        };
                     // display time
-                    draw_label(Pos2::new(650.0, 0.0), 12.0, &self.now_time_display, 600.0);
+                    draw_label(
+                        "time",
+                        Pos2::new(650.0, 0.0),
+                        12.0,
+                        &self.now_time_display,
+                        600.0,
+                    );
                     // display count score
-                    draw_label(Pos2::new(10.0, 0.0), 28.0, &self.dash_board_display, 300.0);
+                    draw_label(
+                        "dashboard",
+                        Pos2::new(10.0, 0.0),
+                        28.0,
+                        &self.dash_board_display,
+                        300.0,
+                    );
 
-                    // control buttons
+                    // control buttons, their text inside the button padding
+                    let padding = ui.spacing().button_padding;
+                    let button_font = egui::FontId::proportional(28.0);
                     // button: start / pause
                     let start_pause_pos = Pos2::new(700.0, 40.0);
                     let start_pause_rect =
                         egui::Rect::from_min_size(start_pause_pos, egui::vec2(40.0, 40.0));
+                    let icon = match &self.player {
+                        Some(player) if player.is_playing() => GameState::Running.icon(),
+                        Some(_) => GameState::Paused.icon(),
+                        None => self.vm.state.icon(),
+                    };
+                    self.overflow.check_width(
+                        ui.ctx(),
+                        "start/pause button",
+                        icon,
+                        button_font.clone(),
+                        start_pause_rect.shrink2(padding),
+                    );
                     if ui
                         .put(
                             start_pause_rect,
-                            egui::Button::new(RichText::new(self.vm.state.icon()).size(28.0)),
+                            egui::Button::new(RichText::new(icon).size(28.0)),
                         )
                         .clicked()
+                        && self.popup == Popup::None
                     {
-                        if self.popup == Popup::None {
-                            self.vm.toggle_run();
+                        match self.player.as_mut() {
+                            Some(player) if player.is_playing() => player.pause(&mut self.vm),
+                            Some(player) => player.play(&mut self.vm),
+                            None => self.vm.toggle_run(),
                         }
                     }
 
                     // button stop
                     let stop_pos = Pos2::new(750.0, 40.0);
                     let stop_rect = egui::Rect::from_min_size(stop_pos, egui::vec2(40.0, 40.0));
+                    self.overflow.check_width(
+                        ui.ctx(),
+                        "stop button",
+                        "⏹",
+                        button_font,
+                        stop_rect.shrink2(padding),
+                    );
                     if ui
                         .put(stop_rect, egui::Button::new(RichText::new("⏹").size(28.0)))
                         .clicked()
+                        && self.popup == Popup::None
                     {
-                        if self.popup == Popup::None {
+                        if self.player.take().is_some() {
+                            // leave playback to a new game
+                            self.vm = FlyViewModel::new(WorldConfig::default());
+                        } else {
                             self.check_exit = self.vm.stop();
                         }
                     }
                 });
             });
 
-        // difficulty radio
-        self.show_difficulty_radio_on_canvas(ctx);
+        // difficulty radio, or the replay bar in playback
+        if self.player.is_some() {
+            self.show_replay_bar(ctx);
+        } else {
+            self.show_difficulty_radio_on_canvas(ctx);
+        }
 
         // language select
         self.language_selector(ctx);
+        // skin select
+        self.skin_selector(ctx);
 
         // draw fly
         self.draw_rustacean(ctx);
@@ -361,28 +894,32 @@ impl eframe::App for MyApp {
                 let ok = &self.ui_config.gameover.close_ok.clone();
                 let message = &self.ui_config.gameover.close_bye.clone();
                 let icon = IconType::Info;
-                self.show_popup(ctx, title, &message, icon, ok);
+                self.show_popup(ctx, title, message, icon, ok);
             }
             Popup::GameOver => {
                 // report score
                 let score = self.vm.get_game_result_message();
                 let title = &self.ui_config.gameover.title.clone();
                 let ok = &self.ui_config.gameover.ok.clone();
-                let (message, icon) = match score {
-                    0 => (self.ui_config.gameover.error.clone(), IconType::Error),
-                    1 => (self.ui_config.gameover.warning.clone(), IconType::Warning),
-                    _ => (
-                        self.ui_config
-                            .gameover
-                            .info
-                            .replace("{score}", &score.to_string()),
-                        IconType::Custom("🏆"),
-                    ),
+                let message = self.ui_config.format(
+                    &self.ui_config.gameover.info,
+                    &Args::new().with("score", score),
+                );
+                let icon = match score {
+                    0 => IconType::Error,
+                    1 => IconType::Warning,
+                    _ => IconType::Custom("🏆"),
                 };
                 self.show_popup(ctx, title, &message, icon, ok);
             }
             Popup::None => {}
         }
+        self.show_storage_warning(ctx);
+        self.show_error_dialog(ctx);
+        // outline the clipped labels while testing the layout
+        if cfg!(debug_assertions) || self.language.code == PSEUDO_LOCALE {
+            self.overflow.paint(ctx);
+        }
         // draw fly hammer
         self.draw_fly_hand(ctx);
 
@@ -1,30 +1,62 @@
 // view model
-use crate::config::{Difficulty, FlyMode, GameState, WorldConfig};
-use crate::fly_model::FlyModel;
+use crate::config::{Difficulty, GameMode, GameState, WorldConfig};
+use crate::fly_behaviour::{Evade, FlyBehaviour, behaviour_pool};
+use crate::fly_model::{Fly, FlyModel};
+use crate::game_clock::GameClock;
+use crate::replay::{Replay, ReplayEvent};
+use rand::RngCore;
 use util::utilities;
 
+/// Ticks per second of the game
+pub const TICK_RATE: f32 = 10.0;
+
 pub struct FlyViewModel {
-    pub timer_tick: f32,
+    pub clock: GameClock, // fixed-timestep game clock
     pub state: GameState, // fly state
     pub fly: FlyModel,
-    pub fly_x: f32, // fly at x
-    pub fly_y: f32, // fly at y
+    pub flies: Vec<Fly>,         // flies on the beach
+    next_fly_id: u32,            // id of the next spawned fly
+    pointer: Option<(f32, f32)>, // cursor position in fly coordinates
     pub difficulty: Difficulty,
+    pub mode: GameMode,
+    tick_count: u64,                      // ticks run in the round
+    recorder: Option<Replay>,             // replay of the running round
+    recorded_pointer: Option<(f32, f32)>, // last pointer in the replay
+    last_replay: Option<Replay>,          // replay of the last finished round
 }
 
 impl FlyViewModel {
     pub fn new(config: WorldConfig) -> Self {
-        let fly = FlyModel::new(config);
-        let x = config.width / 2.0;
-        let y = config.height / 2.0;
+        Self::with_model(config, FlyModel::new(config))
+    }
+
+    /// Create the view model with a fixed seed, for reproducible rounds
+    pub fn with_seed(config: WorldConfig, seed: u64) -> Self {
+        Self::with_model(config, FlyModel::with_seed(config, seed))
+    }
+
+    /// Create the view model with an injected random source
+    pub fn with_rng(config: WorldConfig, rng: Box<dyn RngCore>) -> Self {
+        Self::with_model(config, FlyModel::with_rng(config, rng))
+    }
+
+    fn with_model(config: WorldConfig, fly: FlyModel) -> Self {
+        // first fly waits in the middle of window
+        let first = Fly::new(0, config.width / 2.0, config.height / 2.0);
 
         Self {
-            timer_tick: 0.1, // timer tick 0.1
+            clock: GameClock::new(TICK_RATE),
             state: GameState::Stopped,
             fly,
-            fly_x: x,
-            fly_y: y,
+            flies: vec![first],
+            next_fly_id: 1,
+            pointer: None,
             difficulty: Difficulty::Easy,
+            mode: GameMode::Classic,
+            tick_count: 0,
+            recorder: None,
+            recorded_pointer: None,
+            last_replay: None,
         }
     }
 
@@ -32,14 +64,37 @@ impl FlyViewModel {
     pub fn toggle_run(&mut self) {
         self.state = match self.state {
             GameState::Stopped => {
-                self.fly.fly_start();
+                let seed = self.fly.next_seed();
+                self.start_round(seed);
                 GameState::Running
             }
-            GameState::Running => GameState::Paused,
+            GameState::Running => {
+                // hold the flies still while paused
+                self.settle_flies();
+                GameState::Paused
+            }
             GameState::Paused => GameState::Running,
         };
     }
 
+    // start a new round from the seed and record its replay
+    // the same seed and settings play the same round
+    pub fn start_round(&mut self, seed: u64) {
+        self.fly.reseed(seed);
+        self.fly.fly_start();
+        self.next_fly_id = 0;
+        self.spawn_flies();
+        self.tick_count = 0;
+        self.recorded_pointer = None;
+        self.recorder = Some(Replay::new(
+            seed,
+            self.difficulty,
+            self.fly.config,
+            self.clock.tick_rate(),
+        ));
+        self.state = GameState::Running;
+    }
+
     // stop button procedure
     // return to close the app or not
     pub fn stop(&mut self) -> bool {
@@ -49,16 +104,29 @@ impl FlyViewModel {
         } else {
             // stop flying
             self.state = GameState::Stopped;
+            // a round stopped by hand has no replay
+            self.recorder = None;
             // reset the game
             self.fly.fly_reset();
             // get new position
-            (self.fly_x, self.fly_y) =
-                self.fly
-                    .fly_position(self.fly_x, self.fly_y, FlyMode::Reborn);
+            for fly in self.flies.iter_mut() {
+                self.fly.fly_reborn(fly);
+            }
             false
         }
     }
 
+    // run the ticks due on the clock
+    // return (ticks run, pop message box or not)
+    pub fn update(&mut self) -> (u32, bool) {
+        let ticks = self.clock.update();
+        let mut game_over = false;
+        for _ in 0..ticks {
+            game_over |= self.tick();
+        }
+        (ticks, game_over)
+    }
+
     // timer tick
     // return to pop message box or not or not
     pub fn tick(&mut self) -> bool {
@@ -66,31 +134,93 @@ impl FlyViewModel {
         if !self.is_running() {
             return false;
         }
+        self.record_pointer();
+        self.tick_count += 1;
         // update counter
-        if !self.fly.fly_counter_update(self.timer_tick) {
+        if !self.fly.fly_counter_update(self.clock.tick_dt()) {
+            self.finish_replay();
             self.stop();
             // pop message box
             return true;
         }
-        // get the fly fly trace
-        (self.fly_x, self.fly_y) = self
-            .fly
-            .fly_position(self.fly_x, self.fly_y, FlyMode::Wander);
+        // get the flies fly trace
+        for fly in self.flies.iter_mut() {
+            self.fly.fly_move(fly, self.pointer);
+        }
         false
     }
 
     // fly clicked, fly hunted
-    pub fn fly_click(&mut self) {
-        if !self.is_running() {
+    // the hunted fly leaves the beach and a new one comes
+    pub fn fly_click(&mut self, id: u32) {
+        if !self.is_running() || !self.despawn_fly(id) {
             return;
         }
+        // record the click
+        if let Some(replay) = self.recorder.as_mut() {
+            let (x, y) = self.pointer.unwrap_or_default();
+            replay.events.push(ReplayEvent::Click {
+                tick: self.tick_count,
+                id,
+                x,
+                y,
+            });
+        }
         // update fly hunted number
         self.fly.fly_hunted_update();
 
-        // get a new position
-        (self.fly_x, self.fly_y) = self
-            .fly
-            .fly_position(self.fly_x, self.fly_y, FlyMode::Reborn);
+        // a new fly at a new position
+        self.spawn_fly();
+    }
+
+    // interpolation factor between the previous and the current tick
+    // flies only move while running, so show them where they are otherwise
+    pub fn interpolation(&self) -> f32 {
+        if self.is_running() {
+            self.clock.alpha()
+        } else {
+            1.0
+        }
+    }
+
+    // hold the flies at their current position
+    pub fn settle_flies(&mut self) {
+        for fly in self.flies.iter_mut() {
+            fly.settle();
+        }
+    }
+
+    // ticks run in the round
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    // pointer position in fly coordinates
+    pub fn pointer(&self) -> Option<(f32, f32)> {
+        self.pointer
+    }
+
+    // take the replay of the last finished round
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        self.last_replay.take()
+    }
+
+    // tell the pointer position of this frame
+    // the position is in fly coordinates, None when the pointer is outside
+    pub fn set_pointer(&mut self, pointer: Option<(f32, f32)>) {
+        self.pointer = pointer;
+    }
+
+    // switch the movement behaviour of a fly, still evading the cursor
+    // return false: no fly with the id
+    pub fn set_fly_behaviour(&mut self, id: u32, behaviour: Box<dyn FlyBehaviour>) -> bool {
+        match self.flies.iter_mut().find(|fly| fly.id == id) {
+            Some(fly) => {
+                fly.set_behaviour(Box::new(Evade::new(behaviour)));
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn apply_difficulty(&mut self) {
@@ -99,6 +229,20 @@ impl FlyViewModel {
             Difficulty::Medium => self.fly.fly_speed = 30.0,
             Difficulty::Hard => self.fly.fly_speed = 60.0,
         }
+        self.fly.evasion.strength = self.difficulty.evasion_strength();
+        // show the flies of the difficulty before the game starts
+        if self.state == GameState::Stopped && self.flies.len() != self.fly_count() {
+            self.spawn_flies();
+        }
+    }
+
+    // number of flies on the beach at the same time
+    pub fn fly_count(&self) -> usize {
+        match self.difficulty {
+            Difficulty::Easy => 1,
+            Difficulty::Medium => 2,
+            Difficulty::Hard => 3,
+        }
     }
 
     // set game result message
@@ -106,6 +250,53 @@ impl FlyViewModel {
         self.fly.fly_hunted
     }
     // private functions
+    // replace all flies with new ones
+    fn spawn_flies(&mut self) {
+        self.flies.clear();
+        for _ in 0..self.fly_count() {
+            self.spawn_fly();
+        }
+    }
+
+    // add a new fly to the beach
+    fn spawn_fly(&mut self) {
+        let pool = behaviour_pool(self.difficulty);
+        let fly = self.fly.fly_spawn(self.next_fly_id, pool);
+        self.next_fly_id += 1;
+        self.flies.push(fly);
+    }
+
+    // record the pointer when it changed since the last tick
+    // only the pointer on ticks moves the flies
+    fn record_pointer(&mut self) {
+        if let Some(replay) = self.recorder.as_mut()
+            && self.pointer != self.recorded_pointer
+        {
+            replay.events.push(ReplayEvent::Pointer {
+                tick: self.tick_count,
+                pos: self.pointer,
+            });
+            self.recorded_pointer = self.pointer;
+        }
+    }
+
+    // the round is over, keep its replay
+    fn finish_replay(&mut self) {
+        if let Some(mut replay) = self.recorder.take() {
+            replay.ticks = self.tick_count;
+            replay.score = self.fly.fly_hunted;
+            self.last_replay = Some(replay);
+        }
+    }
+
+    // remove the fly from the beach
+    // return false: no fly with the id
+    fn despawn_fly(&mut self, id: u32) -> bool {
+        let count = self.flies.len();
+        self.flies.retain(|fly| fly.id != id);
+        self.flies.len() != count
+    }
+
     // check game state
     fn is_running(&self) -> bool {
         matches!(self.state, GameState::Running)
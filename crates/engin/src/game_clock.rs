@@ -0,0 +1,163 @@
+// game clock
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// TimeSource: where the game clock reads the real time
+pub trait TimeSource {
+    /// Time elapsed since the source started
+    fn now(&self) -> Duration;
+}
+
+/// SystemTimeSource: the monotonic system clock
+pub struct SystemTimeSource {
+    start: Instant,
+}
+
+impl SystemTimeSource {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemTimeSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// ManualTimeSource: a fake clock moved by hand, for tests
+///
+/// Clones share the same time, so keep one clone to move the time of the
+/// clock that owns the other.
+///
+/// # Examples
+/// ```
+/// use engin::game_clock::{GameClock, ManualTimeSource};
+/// use std::time::Duration;
+///
+/// let time = ManualTimeSource::default();
+/// let mut clock = GameClock::with_source(10.0, Box::new(time.clone()));
+/// assert_eq!(clock.update(), 0);
+/// time.advance(Duration::from_millis(250));
+/// assert_eq!(clock.update(), 2);
+/// ```
+#[derive(Clone, Default)]
+pub struct ManualTimeSource {
+    now: Rc<Cell<Duration>>,
+}
+
+impl ManualTimeSource {
+    /// Move the time forward
+    pub fn advance(&self, dt: Duration) {
+        self.now.set(self.now.get() + dt);
+    }
+}
+
+impl TimeSource for ManualTimeSource {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+/// Shortest tick, a larger or infinite tick rate is clamped to it
+pub const MIN_TICK: Duration = Duration::from_micros(100);
+
+/// GameClock: fixed-timestep accumulator driven by the real frame time
+///
+/// Each frame adds the elapsed real time to the accumulator, and every
+/// full tick in it is one simulation step. A stall is caught up on the
+/// next frame, up to `max_frame` so a long freeze does not burst the game.
+pub struct GameClock {
+    source: Box<dyn TimeSource>,
+    tick: Duration,         // fixed time step
+    max_frame: Duration,    // longest frame time to catch up
+    last: Option<Duration>, // source time of the last update
+    accumulator: Duration,  // real time not simulated yet
+}
+
+impl GameClock {
+    /// Create a clock on the system time with the tick rate in Hz
+    pub fn new(tick_rate: f32) -> Self {
+        Self::with_source(tick_rate, Box::new(SystemTimeSource::new()))
+    }
+
+    /// Create a clock on the time source with the tick rate in Hz
+    pub fn with_source(tick_rate: f32, source: Box<dyn TimeSource>) -> Self {
+        Self {
+            source,
+            tick: Self::tick_of(tick_rate),
+            max_frame: Duration::from_secs(1),
+            last: None,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Seconds of one tick
+    pub fn tick_dt(&self) -> f32 {
+        self.tick.as_secs_f32()
+    }
+
+    /// Ticks per second
+    pub fn tick_rate(&self) -> f32 {
+        1.0 / self.tick.as_secs_f32()
+    }
+
+    /// Change the ticks per second
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        self.tick = Self::tick_of(tick_rate);
+    }
+
+    /// Change the longest frame time to catch up after a stall
+    pub fn set_max_frame(&mut self, max_frame: Duration) {
+        self.max_frame = max_frame;
+    }
+
+    /// Read the time source and return the number of ticks due
+    ///
+    /// The first update only starts the clock.
+    pub fn update(&mut self) -> u32 {
+        let now = self.source.now();
+        let frame = match self.last {
+            Some(last) => now.saturating_sub(last),
+            None => Duration::ZERO,
+        };
+        self.last = Some(now);
+        self.advance(frame)
+    }
+
+    /// Add the frame time and return the number of ticks due
+    pub fn advance(&mut self, frame: Duration) -> u32 {
+        self.accumulator += frame.min(self.max_frame);
+        let mut ticks = 0;
+        while self.accumulator >= self.tick {
+            self.accumulator -= self.tick;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// Progress to the next tick, 0.0..1.0, to interpolate the rendering
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f64() / self.tick.as_secs_f64()) as f32
+    }
+
+    /// Drop the time not simulated yet
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+        self.last = None;
+    }
+
+    // tick duration of the rate, never shorter than MIN_TICK
+    fn tick_of(tick_rate: f32) -> Duration {
+        Duration::from_secs_f64(1.0 / f64::from(tick_rate.max(f32::EPSILON))).max(MIN_TICK)
+    }
+}
@@ -1,6 +1,11 @@
 #![doc = include_str!("../README.md")]
 
 pub mod config;
+pub mod fly_behaviour;
 pub mod fly_model;
 pub mod fly_view;
 pub mod fly_viewmodel;
+pub mod game_clock;
+pub mod overflow;
+pub mod replay;
+pub mod simulation;
@@ -0,0 +1,136 @@
+// overflow
+// The view places its labels in fixed rectangles, a long translation
+// overflows them silently. Each frame the view measures the text of those
+// labels against their rectangle; every clipped label is logged once and
+// kept for the frame, so the view can outline it.
+use eframe::egui::{self, Color32, FontId, Rect, Vec2};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Overflow: a label larger than its rectangle
+#[derive(Clone, Debug, PartialEq)]
+pub struct Overflow {
+    /// Name of the widget
+    pub widget: String,
+    pub text: String,
+    /// Size the text needs
+    pub needed: Vec2,
+    /// Rectangle the view gives the text
+    pub rect: Rect,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} clips its text, needs {:.0}x{:.0} in {:.0}x{:.0}: {:?}",
+            self.widget,
+            self.needed.x,
+            self.needed.y,
+            self.rect.width(),
+            self.rect.height(),
+            self.text
+        )
+    }
+}
+
+/// OverflowDetector: the clipped labels of the frame
+#[derive(Default)]
+pub struct OverflowDetector {
+    current: Vec<Overflow>,
+    reported: HashSet<(String, String)>,
+}
+
+impl OverflowDetector {
+    /// A detector with no clipped labels and nothing logged yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget the labels of the last frame
+    pub fn begin_frame(&mut self) {
+        self.current.clear();
+    }
+
+    /// Check a label against its rectangle, a clipped label is logged the first time
+    ///
+    /// # Returns
+    ///
+    /// * true when the text does not fit
+    pub fn check(&mut self, widget: &str, text: &str, needed: Vec2, rect: Rect) -> bool {
+        // half a point of rounding is not a clip
+        let clipped = needed.x > rect.width() + 0.5 || needed.y > rect.height() + 0.5;
+        if clipped {
+            let overflow = Overflow {
+                widget: widget.to_string(),
+                text: text.to_string(),
+                needed,
+                rect,
+            };
+            if self.reported.insert((widget.to_string(), text.to_string())) {
+                log::warn!("{overflow}");
+            }
+            self.current.push(overflow);
+        }
+        clipped
+    }
+
+    /// Measure the text, wrapped at `wrap_width`, and check it against its rectangle
+    pub fn check_text(
+        &mut self,
+        ctx: &egui::Context,
+        widget: &str,
+        text: &str,
+        font: FontId,
+        wrap_width: f32,
+        rect: Rect,
+    ) -> bool {
+        let needed = text_size(ctx, text, font, wrap_width);
+        self.check(widget, text, needed, rect)
+    }
+
+    /// Measure the text on one line and check its width against its rectangle,
+    /// for labels in a row that can grow down
+    pub fn check_width(
+        &mut self,
+        ctx: &egui::Context,
+        widget: &str,
+        text: &str,
+        font: FontId,
+        rect: Rect,
+    ) -> bool {
+        let needed = text_size(ctx, text, font, f32::INFINITY);
+        let needed = Vec2::new(needed.x, needed.y.min(rect.height()));
+        self.check(widget, text, needed, rect)
+    }
+
+    /// The clipped labels of the frame
+    pub fn overflows(&self) -> &[Overflow] {
+        &self.current
+    }
+
+    /// Outline the clipped labels of the frame
+    pub fn paint(&self, ctx: &egui::Context) {
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("overflow"),
+        ));
+        for overflow in &self.current {
+            painter.rect_stroke(
+                overflow.rect,
+                0.0,
+                egui::Stroke::new(2.0, Color32::RED),
+                egui::StrokeKind::Outside,
+            );
+        }
+    }
+}
+
+/// Size of the text laid out in the font, wrapped at `wrap_width`
+pub fn text_size(ctx: &egui::Context, text: &str, font: FontId, wrap_width: f32) -> Vec2 {
+    ctx.fonts_mut(|fonts| {
+        fonts
+            .layout(text.to_string(), font, Color32::WHITE, wrap_width)
+            .size()
+    })
+}
@@ -0,0 +1,259 @@
+// replay
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Difficulty, WorldConfig};
+use crate::fly_viewmodel::FlyViewModel;
+
+/// Version of the replay file format
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Replays kept in the replay directory, the oldest are removed first
+pub const KEPT_REPLAYS: usize = 20;
+
+/// Tick rates a replay file may ask for, in Hz
+pub const REPLAY_TICK_RATES: RangeInclusive<f32> = 1.0..=240.0;
+
+/// ReplayEvent: a player input and the tick it happened on
+///
+/// `tick` is the number of ticks already run when the input came in.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayEvent {
+    /// The pointer moved, in fly coordinates, None when outside the window
+    Pointer { tick: u64, pos: Option<(f32, f32)> },
+    /// The fly with the id was clicked at the position
+    Click { tick: u64, id: u32, x: f32, y: f32 },
+}
+
+impl ReplayEvent {
+    /// The tick the event happened on
+    pub fn tick(&self) -> u64 {
+        match self {
+            ReplayEvent::Pointer { tick, .. } | ReplayEvent::Click { tick, .. } => *tick,
+        }
+    }
+}
+
+/// Replay: everything needed to play a round again
+///
+/// The round seed, the settings and the player inputs rebuild the same
+/// round tick by tick.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    /// Replay file format version
+    pub version: u32,
+    /// Seed of the round
+    pub seed: u64,
+    /// Difficulty of the round
+    pub difficulty: Difficulty,
+    /// World configuration of the round
+    pub world: WorldConfig,
+    /// Ticks per second of the round
+    pub tick_rate: f32,
+    /// Total ticks of the round
+    pub ticks: u64,
+    /// Flies hunted in the round
+    pub score: u32,
+    /// Player inputs in order
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// Start recording a round
+    pub fn new(seed: u64, difficulty: Difficulty, world: WorldConfig, tick_rate: f32) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            difficulty,
+            world,
+            tick_rate,
+            ticks: 0,
+            score: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Save the replay as a compact JSON file
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let data = serde_json::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, data)
+    }
+
+    /// Save the replay in the directory and remove the oldest past `keep`
+    ///
+    /// The file is named after the time and the seed of the round,
+    /// `flyrust-20260101-120000.123-<seed>.json`, so two rounds ending in the
+    /// same second do not overwrite each other. Only files of that pattern
+    /// are removed.
+    ///
+    /// # Returns
+    ///
+    /// * the path of the saved replay
+    pub fn save_in(&self, dir: &Path, keep: usize) -> io::Result<PathBuf> {
+        let time = Local::now().format("%Y%m%d-%H%M%S%.3f");
+        let path = dir.join(format!("flyrust-{time}-{:016x}.json", self.seed));
+        self.save(&path)?;
+
+        // the names sort by time
+        let mut saved: Vec<PathBuf> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|old| {
+                old != &path
+                    && old
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("flyrust-") && name.ends_with(".json"))
+            })
+            .collect();
+        saved.sort();
+        let extra = (saved.len() + 1).saturating_sub(keep.max(1));
+        for old in &saved[..extra] {
+            fs::remove_file(old)?;
+        }
+        Ok(path)
+    }
+
+    /// Load a replay file
+    ///
+    /// # Errors
+    /// - Returns an error if the file cannot be read or parsed
+    /// - Returns an error if the replay is newer than the game
+    /// - Returns an error if the tick rate is not in `REPLAY_TICK_RATES`
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let data = fs::read_to_string(path)?;
+        let replay: Self = serde_json::from_str(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if replay.version > REPLAY_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("replay version {} is not supported", replay.version),
+            ));
+        }
+        // replays are shared, a huge rate would stall the clock
+        if !REPLAY_TICK_RATES.contains(&replay.tick_rate) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("replay tick rate {} is not supported", replay.tick_rate),
+            ));
+        }
+        Ok(replay)
+    }
+}
+
+/// ReplayPlayer: feed a replay back through a FlyViewModel
+///
+/// The player drives the view model given to it, so the view draws the
+/// replay the same way it draws a live round.
+pub struct ReplayPlayer {
+    replay: Replay,
+    next_event: usize, // first event not fed yet
+    playing: bool,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_event: 0,
+            playing: true,
+        }
+    }
+
+    /// The replay being played
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// A view model at the start of the replay round
+    pub fn start(&mut self) -> FlyViewModel {
+        let replay = &self.replay;
+        let mut vm = FlyViewModel::new(replay.world);
+        vm.clock.set_tick_rate(replay.tick_rate);
+        vm.difficulty = replay.difficulty;
+        vm.apply_difficulty();
+        vm.start_round(replay.seed);
+        self.next_event = 0;
+        vm
+    }
+
+    /// Run the ticks due on the clock of the view model while playing
+    pub fn update(&mut self, vm: &mut FlyViewModel) {
+        let ticks = vm.clock.update();
+        if !self.playing {
+            return;
+        }
+        for _ in 0..ticks {
+            if !self.step(vm) {
+                self.playing = false;
+                break;
+            }
+        }
+    }
+
+    /// Feed the inputs of the next tick and run it
+    /// return false: the replay is over
+    pub fn step(&mut self, vm: &mut FlyViewModel) -> bool {
+        if self.is_finished(vm) {
+            return false;
+        }
+        let tick = vm.tick_count();
+        while let Some(event) = self.replay.events.get(self.next_event) {
+            if event.tick() != tick {
+                break;
+            }
+            match *event {
+                ReplayEvent::Pointer { pos, .. } => vm.set_pointer(pos),
+                ReplayEvent::Click { id, .. } => vm.fly_click(id),
+            }
+            self.next_event += 1;
+        }
+        vm.tick();
+        true
+    }
+
+    /// Jump to the tick, by playing the round again up to it
+    pub fn seek(&mut self, vm: &mut FlyViewModel, tick: u64) {
+        let target = tick.min(self.replay.ticks);
+        if target < vm.tick_count() {
+            *vm = self.start();
+        }
+        while vm.tick_count() < target && self.step(vm) {}
+        vm.settle_flies();
+    }
+
+    /// Play, from the start again when the replay is over
+    pub fn play(&mut self, vm: &mut FlyViewModel) {
+        if self.is_finished(vm) {
+            self.seek(vm, 0);
+        }
+        self.playing = true;
+    }
+
+    /// Pause, holding the flies still
+    pub fn pause(&mut self, vm: &mut FlyViewModel) {
+        self.playing = false;
+        vm.settle_flies();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// All ticks of the replay are played
+    pub fn is_finished(&self, vm: &FlyViewModel) -> bool {
+        vm.tick_count() >= self.replay.ticks
+    }
+
+    /// Interpolation factor to draw the flies
+    pub fn interpolation(&self, vm: &FlyViewModel) -> f32 {
+        if self.playing { vm.clock.alpha() } else { 1.0 }
+    }
+}
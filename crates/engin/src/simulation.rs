@@ -0,0 +1,301 @@
+// simulation
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::f32::consts::TAU;
+use util::skin::DEFAULT_FLY_SIZE;
+
+use crate::config::{Difficulty, WorldConfig};
+use crate::fly_viewmodel::FlyViewModel;
+
+/// BotConfig: how well the auto-play bot plays
+#[derive(Clone, Copy, Debug)]
+pub struct BotConfig {
+    /// Ticks the bot waits before clicking a new target
+    pub reaction_ticks: u32,
+    /// Max distance in pixels between the aim and the target
+    pub aim_error: f32,
+    /// Half size of the fly hit box in pixels
+    pub hit_radius: f32,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            reaction_ticks: 3,
+            aim_error: 20.0,
+            hit_radius: DEFAULT_FLY_SIZE / 2.0,
+        }
+    }
+}
+
+/// Bot: a scripted player that clicks flies through `fly_click`
+pub struct Bot {
+    config: BotConfig,
+    rng: StdRng,
+    target: Option<u32>, // id of the fly the bot is after
+    seen: (f32, f32),    // where the bot saw the target when reacting
+    wait: u32,           // ticks left before the click
+    pub clicks: u32,     // clicks of the round
+    pub misses: u32,     // clicks that missed
+}
+
+impl Bot {
+    pub fn new(config: BotConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            target: None,
+            seen: (0.0, 0.0),
+            wait: config.reaction_ticks,
+            clicks: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look at the beach and maybe click, once per tick
+    ///
+    /// The bot clicks where it saw the fly when it started to react, so a
+    /// fast or evading fly can be gone by the time of the click.
+    pub fn act(&mut self, vm: &mut FlyViewModel) {
+        // lost the target, react to a new one
+        let target = self
+            .target
+            .and_then(|id| vm.flies.iter().find(|fly| fly.id == id));
+        let Some(fly) = target.or_else(|| vm.flies.first()) else {
+            return;
+        };
+        if self.target != Some(fly.id) {
+            self.target = Some(fly.id);
+            self.seen = (fly.x, fly.y);
+            self.wait = self.config.reaction_ticks;
+        }
+        if self.wait > 0 {
+            self.wait -= 1;
+            return;
+        }
+
+        // aim somewhere around where the fly was seen
+        let angle = self.rng.random_range(0.0..TAU);
+        let distance = self.rng.random_range(0.0..=1.0) * self.config.aim_error;
+        let aim = (
+            self.seen.0 + angle.cos() * distance,
+            self.seen.1 + angle.sin() * distance,
+        );
+        let hit = (aim.0 - fly.x).abs() <= self.config.hit_radius
+            && (aim.1 - fly.y).abs() <= self.config.hit_radius;
+        let (id, fly_x, fly_y) = (fly.id, fly.x, fly.y);
+
+        vm.set_pointer(Some(aim));
+        self.clicks += 1;
+        if hit {
+            vm.fly_click(id);
+            self.target = None;
+        } else {
+            // try again after reacting to the miss
+            self.misses += 1;
+            self.seen = (fly_x, fly_y);
+            self.wait = self.config.reaction_ticks;
+        }
+    }
+}
+
+/// SimulationConfig: a batch of headless rounds
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    /// Rounds per difficulty
+    pub rounds: u32,
+    /// Seed of the first round, the next rounds count up from it
+    pub seed: u64,
+    /// The bot playing the rounds
+    pub bot: BotConfig,
+    /// Override the fly speed of the difficulty, to tune it
+    pub speed: Option<f32>,
+    /// Difficulties to play
+    pub difficulties: Vec<Difficulty>,
+    /// World configuration
+    pub world: WorldConfig,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            rounds: 1000,
+            seed: 0,
+            bot: BotConfig::default(),
+            speed: None,
+            difficulties: vec![Difficulty::Easy, Difficulty::Medium, Difficulty::Hard],
+            world: WorldConfig::default(),
+        }
+    }
+}
+
+/// RoundResult: the outcome of one headless round
+#[derive(Clone, Debug, Serialize)]
+pub struct RoundResult {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub speed: f32,
+    pub score: u32,
+    pub clicks: u32,
+    pub misses: u32,
+}
+
+/// ScoreStats: score distribution of one difficulty
+#[derive(Clone, Debug, Serialize)]
+pub struct ScoreStats {
+    pub difficulty: Difficulty,
+    pub speed: f32,
+    pub rounds: usize,
+    pub mean: f32,
+    pub std_dev: f32,
+    pub min: u32,
+    pub p50: u32,
+    pub p90: u32,
+    pub max: u32,
+    pub hit_rate: f32,
+}
+
+/// Play one round without the view, the bot clicks the flies
+pub fn run_round(
+    world: WorldConfig,
+    difficulty: Difficulty,
+    seed: u64,
+    bot: BotConfig,
+    speed: Option<f32>,
+) -> RoundResult {
+    let mut vm = FlyViewModel::with_seed(world, seed);
+    vm.difficulty = difficulty;
+    vm.apply_difficulty();
+    if let Some(speed) = speed {
+        vm.fly.fly_speed = speed;
+    }
+    let mut bot = Bot::new(bot, seed);
+
+    vm.toggle_run();
+    loop {
+        bot.act(&mut vm);
+        if vm.tick() {
+            break;
+        }
+    }
+
+    RoundResult {
+        seed,
+        difficulty,
+        speed: vm.fly.fly_speed,
+        score: vm.get_game_result_message(),
+        clicks: bot.clicks,
+        misses: bot.misses,
+    }
+}
+
+/// Play all rounds of the simulation
+pub fn run_batch(config: &SimulationConfig) -> Vec<RoundResult> {
+    let mut results = Vec::new();
+    for &difficulty in &config.difficulties {
+        for n in 0..config.rounds {
+            let seed = config.seed.wrapping_add(u64::from(n));
+            results.push(run_round(
+                config.world,
+                difficulty,
+                seed,
+                config.bot,
+                config.speed,
+            ));
+        }
+    }
+    results
+}
+
+/// Score distribution per difficulty, in the order the difficulties appear
+pub fn score_stats(results: &[RoundResult]) -> Vec<ScoreStats> {
+    let mut difficulties: Vec<Difficulty> = Vec::new();
+    for result in results {
+        if !difficulties.contains(&result.difficulty) {
+            difficulties.push(result.difficulty);
+        }
+    }
+
+    difficulties
+        .into_iter()
+        .map(|difficulty| {
+            let rounds: Vec<&RoundResult> = results
+                .iter()
+                .filter(|r| r.difficulty == difficulty)
+                .collect();
+            let mut scores: Vec<u32> = rounds.iter().map(|r| r.score).collect();
+            scores.sort_unstable();
+            let count = scores.len() as f32;
+            let mean = scores.iter().sum::<u32>() as f32 / count;
+            let variance = scores
+                .iter()
+                .map(|&s| (s as f32 - mean).powi(2))
+                .sum::<f32>()
+                / count;
+            let percentile = |p: f32| scores[((scores.len() - 1) as f32 * p).round() as usize];
+            let clicks: u32 = rounds.iter().map(|r| r.clicks).sum();
+            let misses: u32 = rounds.iter().map(|r| r.misses).sum();
+
+            ScoreStats {
+                difficulty,
+                speed: rounds[0].speed,
+                rounds: scores.len(),
+                mean,
+                std_dev: variance.sqrt(),
+                min: scores[0],
+                p50: percentile(0.5),
+                p90: percentile(0.9),
+                max: scores[scores.len() - 1],
+                hit_rate: if clicks > 0 {
+                    (clicks - misses) as f32 / clicks as f32
+                } else {
+                    0.0
+                },
+            }
+        })
+        .collect()
+}
+
+/// Rounds as CSV with a header line
+pub fn rounds_to_csv(results: &[RoundResult]) -> String {
+    let mut csv = String::from("seed,difficulty,speed,score,clicks,misses\n");
+    for r in results {
+        csv.push_str(&format!(
+            "{},{:?},{},{},{},{}\n",
+            r.seed, r.difficulty, r.speed, r.score, r.clicks, r.misses
+        ));
+    }
+    csv
+}
+
+/// Score distributions as CSV with a header line
+pub fn stats_to_csv(stats: &[ScoreStats]) -> String {
+    let mut csv = String::from("difficulty,speed,rounds,mean,std_dev,min,p50,p90,max,hit_rate\n");
+    for s in stats {
+        csv.push_str(&format!(
+            "{:?},{},{},{:.3},{:.3},{},{},{},{},{:.3}\n",
+            s.difficulty,
+            s.speed,
+            s.rounds,
+            s.mean,
+            s.std_dev,
+            s.min,
+            s.p50,
+            s.p90,
+            s.max,
+            s.hit_rate
+        ));
+    }
+    csv
+}
+
+/// Rounds as a JSON array
+pub fn rounds_to_json(results: &[RoundResult]) -> String {
+    serde_json::to_string_pretty(results).unwrap_or_default()
+}
+
+/// Score distributions as a JSON array
+pub fn stats_to_json(stats: &[ScoreStats]) -> String {
+    serde_json::to_string_pretty(stats).unwrap_or_default()
+}
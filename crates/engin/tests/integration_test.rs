@@ -1,7 +1,362 @@
-// crates/lib/tests/integration_test.rs
-// use util::add;
+// crates/engin/tests/integration_test.rs
+use eframe::egui;
+use engin::config::{Difficulty, EvasionConfig, WorldConfig};
+use engin::fly_behaviour::{BehaviourFactory, BezierGlide, Dash, MoveContext, Orbit, ZigZag};
+use engin::fly_viewmodel::{FlyViewModel, TICK_RATE};
+use engin::game_clock::{GameClock, MIN_TICK, ManualTimeSource};
+use engin::overflow::OverflowDetector;
+use engin::replay::{Replay, ReplayPlayer};
+use engin::simulation::{self, SimulationConfig};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::path::PathBuf;
+use std::time::Duration;
+use util::assets::UiConfig;
+use util::message::Args;
 
-// #[test]
-// fn it_adds_one() {
-//     assert_eq!(add(1, 2), 3);
-// }
+// play a round with the same clicks and collect the fly trace
+fn trace(seed: u64) -> Vec<(f32, f32)> {
+    let mut vm = FlyViewModel::with_seed(WorldConfig::default(), seed);
+    vm.toggle_run();
+    let mut trace = Vec::new();
+    for n in 0..50 {
+        if n % 7 == 0 {
+            let id = vm.flies[0].id;
+            vm.fly_click(id);
+        }
+        vm.tick();
+        trace.extend(vm.flies.iter().map(|fly| (fly.x, fly.y)));
+    }
+    trace
+}
+
+#[test]
+fn same_seed_same_trajectory() {
+    assert_eq!(trace(42), trace(42));
+    assert_ne!(trace(42), trace(43));
+}
+
+#[test]
+fn hunted_fly_is_replaced() {
+    let mut vm = FlyViewModel::with_seed(WorldConfig::default(), 1);
+    vm.difficulty = Difficulty::Hard;
+    vm.apply_difficulty();
+    vm.toggle_run();
+    assert_eq!(vm.flies.len(), 3);
+
+    let id = vm.flies[1].id;
+    vm.fly_click(id);
+    assert_eq!(vm.flies.len(), 3);
+    assert!(vm.flies.iter().all(|fly| fly.id != id));
+    assert_eq!(vm.get_game_result_message(), 1);
+
+    // a fly already gone scores nothing
+    vm.fly_click(id);
+    assert_eq!(vm.get_game_result_message(), 1);
+}
+
+#[test]
+fn fly_switches_behaviour_in_round() {
+    let config = WorldConfig::default();
+    let mut vm = FlyViewModel::with_seed(config, 5);
+    vm.toggle_run();
+    let id = vm.flies[0].id;
+
+    assert!(vm.set_fly_behaviour(id, Box::new(Dash::default())));
+    assert_eq!(vm.flies[0].behaviour_name(), "dash");
+    for _ in 0..30 {
+        vm.tick();
+    }
+    assert!(vm.set_fly_behaviour(id, Box::new(Orbit::default())));
+    assert_eq!(vm.flies[0].behaviour_name(), "orbit");
+    for _ in 0..30 {
+        vm.tick();
+    }
+
+    // the fly never leaves the beach
+    let fly = &vm.flies[0];
+    assert!((config.margin..=config.width - config.margin).contains(&fly.x));
+    assert!((config.margin..=config.height - config.margin).contains(&fly.y));
+}
+
+// positions of a behaviour moving on its own from the center
+fn behaviour_trace(factory: BehaviourFactory, seed: u64) -> Vec<(f32, f32)> {
+    let config = WorldConfig::default();
+    let evasion = EvasionConfig::default();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut behaviour = factory();
+    let mut pos = (config.width / 2.0, config.height / 2.0);
+    let mut trace = Vec::new();
+    for _ in 0..500 {
+        // fast enough to hit the walls
+        let mut ctx = MoveContext {
+            config: &config,
+            speed: 20.0,
+            pointer: None,
+            evasion: &evasion,
+            rng: &mut rng,
+        };
+        pos = behaviour.next_position(pos, &mut ctx);
+        trace.push(pos);
+    }
+    trace
+}
+
+#[test]
+fn glide_and_zig_zag_stay_on_the_beach_and_follow_the_seed() {
+    let config = WorldConfig::default();
+    for factory in [BezierGlide::boxed as BehaviourFactory, ZigZag::boxed] {
+        let name = factory().name();
+        let trace = behaviour_trace(factory, 7);
+        for &(x, y) in &trace {
+            assert!(
+                (config.margin..=config.width - config.margin).contains(&x),
+                "{name}: x {x}"
+            );
+            assert!(
+                (config.margin..=config.height - config.margin).contains(&y),
+                "{name}: y {y}"
+            );
+        }
+        assert_eq!(trace, behaviour_trace(factory, 7), "{name}");
+        assert_ne!(trace, behaviour_trace(factory, 8), "{name}");
+    }
+}
+
+#[test]
+fn fly_runs_away_from_cursor() {
+    let config = WorldConfig::default();
+    let mut vm = FlyViewModel::with_seed(config, 9);
+    vm.difficulty = Difficulty::Hard;
+    vm.apply_difficulty();
+    vm.toggle_run();
+
+    let distance = |vm: &FlyViewModel, (px, py): (f32, f32)| {
+        let fly = &vm.flies[0];
+        ((fly.x - px).powi(2) + (fly.y - py).powi(2)).sqrt()
+    };
+    // the cursor sits right beside the first fly
+    let pointer = (vm.flies[0].x + 10.0, vm.flies[0].y);
+    vm.set_pointer(Some(pointer));
+    let before = distance(&vm, pointer);
+    for _ in 0..10 {
+        vm.tick();
+    }
+    assert!(distance(&vm, pointer) > before);
+}
+
+#[test]
+fn clock_catches_up_after_stall() {
+    let time = ManualTimeSource::default();
+    let mut vm = FlyViewModel::with_seed(WorldConfig::default(), 3);
+    vm.clock = GameClock::with_source(10.0, Box::new(time.clone()));
+    vm.toggle_run();
+    assert_eq!(vm.update(), (0, false));
+
+    // frames shorter than a tick add up
+    for _ in 0..3 {
+        time.advance(Duration::from_millis(40));
+        vm.update();
+    }
+    assert!((vm.fly.counter - 9.9).abs() < 1e-4);
+
+    // a stall of half a second runs the missed ticks at once
+    time.advance(Duration::from_millis(500));
+    assert_eq!(vm.update(), (5, false));
+
+    // the round ends on time
+    let mut game_over = false;
+    for _ in 0..10 {
+        time.advance(Duration::from_secs(1));
+        game_over |= vm.update().1;
+    }
+    assert!(game_over);
+}
+
+#[test]
+fn huge_tick_rates_keep_a_shortest_tick() {
+    for rate in [f32::INFINITY, f32::MAX] {
+        let mut clock = GameClock::with_source(rate, Box::new(ManualTimeSource::default()));
+        assert_eq!(clock.tick_dt(), MIN_TICK.as_secs_f32(), "{rate}");
+        assert!(clock.tick_rate().is_finite(), "{rate}");
+
+        // a stall runs a bounded number of ticks
+        let ticks = clock.advance(Duration::from_secs(1));
+        assert_eq!(ticks as u128, 1_000_000 / MIN_TICK.as_micros(), "{rate}");
+        assert!((0.0..1.0).contains(&clock.alpha()), "{rate}");
+
+        clock.set_tick_rate(10.0);
+        clock.set_tick_rate(rate);
+        assert_eq!(clock.tick_dt(), MIN_TICK.as_secs_f32(), "{rate}");
+    }
+}
+
+#[test]
+fn interpolation_keeps_simulation() {
+    let time = ManualTimeSource::default();
+    let mut smooth = FlyViewModel::with_seed(WorldConfig::default(), 11);
+    smooth.clock = GameClock::with_source(10.0, Box::new(time.clone()));
+    smooth.toggle_run();
+    smooth.update();
+    let mut plain = FlyViewModel::with_seed(WorldConfig::default(), 11);
+    plain.toggle_run();
+
+    // frames at 60 Hz: the drawn fly sits between the two ticks
+    for frame in 1..=60 {
+        time.advance(Duration::from_micros(16_667));
+        let (ticks, _) = smooth.update();
+        for _ in 0..ticks {
+            plain.tick();
+        }
+        let alpha = smooth.interpolation();
+        assert!((0.0..1.0).contains(&alpha), "frame {frame}");
+        let fly = &smooth.flies[0];
+        let (x, _) = fly.lerp_position(alpha);
+        assert!(x >= fly.prev_x.min(fly.x) && x <= fly.prev_x.max(fly.x));
+    }
+    // same result as the ticks without frames
+    assert_eq!(smooth.flies[0].x, plain.flies[0].x);
+    assert_eq!(smooth.flies[0].y, plain.flies[0].y);
+}
+
+#[test]
+fn replay_plays_the_same_round() {
+    let mut vm = FlyViewModel::with_seed(WorldConfig::default(), 21);
+    vm.difficulty = Difficulty::Medium;
+    vm.apply_difficulty();
+    vm.toggle_run();
+    let mut n = 0;
+    while !vm.tick() {
+        n += 1;
+        vm.set_pointer(Some((300.0 + n as f32, 200.0)));
+        if n % 9 == 0 {
+            let id = vm.flies[n % 2].id;
+            vm.fly_click(id);
+        }
+    }
+    let replay = vm.take_replay().expect("finished round has a replay");
+    assert_eq!(replay.ticks, vm.tick_count());
+    assert!(replay.score > 0);
+
+    // the file round trip keeps the round
+    let path = std::env::temp_dir().join("flyrust-test-replay.json");
+    replay.save(&path).unwrap();
+    let replay = Replay::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let mut player = ReplayPlayer::new(replay.clone());
+    let mut playback = player.start();
+    while player.step(&mut playback) {}
+    assert_eq!(playback.get_game_result_message(), replay.score);
+    let positions = |vm: &FlyViewModel| vm.flies.iter().map(|f| (f.x, f.y)).collect::<Vec<_>>();
+    assert_eq!(positions(&playback), positions(&vm));
+
+    // seek back and forth lands on the same tick
+    player.seek(&mut playback, 30);
+    assert_eq!(playback.tick_count(), 30);
+    let at_30 = positions(&playback);
+    player.seek(&mut playback, 60);
+    player.seek(&mut playback, 30);
+    assert_eq!(positions(&playback), at_30);
+
+    // play at the end starts the replay again
+    player.seek(&mut playback, replay.ticks);
+    assert!(player.is_finished(&playback));
+    player.play(&mut playback);
+    assert!(player.is_playing());
+    assert_eq!(playback.tick_count(), 0);
+}
+
+#[test]
+fn replay_with_a_bad_tick_rate_is_refused() {
+    let replay = Replay::new(1, Difficulty::Easy, WorldConfig::default(), TICK_RATE);
+    let data = serde_json::to_string(&replay).unwrap();
+    let path = std::env::temp_dir().join("flyrust-test-tick-rate.json");
+    for rate in ["1e39", "0", "1000", "-10"] {
+        let crafted = data.replace(
+            &format!("\"tick_rate\":{TICK_RATE:?}"),
+            &format!("\"tick_rate\":{rate}"),
+        );
+        assert_ne!(crafted, data);
+        std::fs::write(&path, crafted).unwrap();
+        let err = Replay::load(&path).expect_err(rate);
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{rate}");
+    }
+    std::fs::write(&path, &data).unwrap();
+    assert_eq!(Replay::load(&path).unwrap().tick_rate, TICK_RATE);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn saved_replays_get_their_own_file_and_the_oldest_go() {
+    let dir = std::env::temp_dir().join(format!("flyrust-test-replays-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("shared.json"), "{}").unwrap();
+
+    let mut last = PathBuf::new();
+    for seed in 0..5 {
+        let replay = Replay::new(seed, Difficulty::Easy, WorldConfig::default(), TICK_RATE);
+        let path = replay.save_in(&dir, 3).unwrap();
+        assert_ne!(path, last);
+        assert_eq!(Replay::load(&path).unwrap().seed, seed);
+        last = path;
+    }
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    // three replays, the last one among them, and the file of another name
+    assert_eq!(names.len(), 4, "{names:?}");
+    assert!(last.exists());
+    assert!(names.contains(&"shared.json".to_string()));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn headless_batch_is_reproducible() {
+    let config = SimulationConfig {
+        rounds: 5,
+        ..SimulationConfig::default()
+    };
+    let results = simulation::run_batch(&config);
+    assert_eq!(results.len(), 15);
+    assert_eq!(
+        simulation::rounds_to_csv(&results),
+        simulation::rounds_to_csv(&simulation::run_batch(&config))
+    );
+
+    let stats = simulation::score_stats(&results);
+    assert_eq!(stats.len(), 3);
+    assert!(stats.iter().all(|s| s.rounds == 5 && s.max > 0));
+    assert_eq!(simulation::stats_to_csv(&stats).lines().count(), 4);
+}
+
+#[test]
+fn pseudo_labels_overflow_the_dashboard() {
+    let (english, _) = UiConfig::load_locale("en").unwrap();
+    let pseudo = english.pseudo();
+    let args = Args::new().with("time", "00:42").with("score", 1234);
+    let rect = egui::Rect::from_min_size(egui::pos2(10.0, 0.0), egui::vec2(200.0, 50.0));
+
+    let ctx = egui::Context::default();
+    let mut detector = OverflowDetector::new();
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        let font = egui::FontId::monospace(28.0);
+        for (widget, ui) in [("english", &english), ("pseudo", &pseudo)] {
+            let text = ui.format(&ui.labels.dashboard, &args);
+            detector.check_width(ctx, widget, &text, font.clone(), rect);
+        }
+    });
+    let widgets: Vec<&str> = detector
+        .overflows()
+        .iter()
+        .map(|o| o.widget.as_str())
+        .collect();
+    assert_eq!(widgets, ["pseudo"]);
+    assert!(detector.overflows()[0].needed.x > rect.width());
+
+    detector.begin_frame();
+    assert!(detector.overflows().is_empty());
+}
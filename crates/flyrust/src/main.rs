@@ -2,15 +2,93 @@
 
 // import
 use eframe::egui;
-use egui_extras;
-use engin::config::WorldConfig;
+use engin::config::{Difficulty, ICON_SYMBOLS, WorldConfig};
 use engin::fly_view::MyApp;
+use engin::replay::Replay;
+use engin::simulation::{self, SimulationConfig};
+use std::path::{Path, PathBuf};
+use util::font_check;
+use util::i18n_check::{self, Report};
 use util::what_panic;
 
+// version of the game, kept with the scores
+const APP_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 // main entry point
 fn main() -> eframe::Result<()> {
     // debug: panic information at /tmp/what_panic.log
     what_panic();
+    // translation and other warnings on stderr, RUST_LOG for more
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // data directory: flyrust --data-dir <dir>, or FLYRUST_DATA_DIR
+    if let Some(dir) = arg_value(&args, "--data-dir") {
+        util::data_dir::set_data_dir(PathBuf::from(dir));
+    }
+    // translator locale files: flyrust --i18n-dir <dir>, or FLYRUST_I18N_DIR
+    if let Some(dir) = arg_value(&args, "--i18n-dir") {
+        util::i18n::set_i18n_dir(PathBuf::from(dir));
+    }
+
+    // headless simulation: flyrust simulate [options]
+    if args.first().map(String::as_str) == Some("simulate") {
+        match simulate(&args[1..]) {
+            Ok(output) => println!("{output}"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        }
+        return Ok(());
+    }
+
+    // translation check: flyrust check-i18n [--root <dir>] [--dir <dir>]
+    if args.first().map(String::as_str) == Some("check-i18n") {
+        match check_i18n(&args[1..]) {
+            Ok(report) => {
+                println!("{report}");
+                if !report.is_clean() {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        }
+        return Ok(());
+    }
+
+    // font coverage: flyrust check-fonts
+    if args.first().map(String::as_str) == Some("check-fonts") {
+        match font_check::check_installed(ICON_SYMBOLS) {
+            Ok(report) => {
+                println!("{report}");
+                if !report.is_clean() {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        }
+        return Ok(());
+    }
+
+    // replay playback: flyrust --replay <file>
+    let replay = match arg_value(&args, "--replay").map(PathBuf::from) {
+        Some(path) => match Replay::load(&path) {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                eprintln!("Failed to load replay {}: {}", path.display(), e);
+                None
+            }
+        },
+        None => None,
+    };
 
     // start app
     // initial app windows size
@@ -21,12 +99,105 @@ fn main() -> eframe::Result<()> {
         ..Default::default()
     };
 
+    // starting locale: flyrust --locale <code>, en-XA for the pseudo-locale
+    let locale = arg_value(&args, "--locale").map(str::to_string);
+
     eframe::run_native(
         "Fly Rust", // my app name
         options,
         Box::new(|cc| {
             egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(MyApp::new(cc)))
+            let mut app = match replay {
+                Some(replay) => MyApp::with_replay(cc, APP_VERSION, replay),
+                None => MyApp::new(cc, APP_VERSION),
+            };
+            if let Some(code) = locale {
+                app.select_locale(&code);
+            }
+            Ok(Box::new(app))
         }),
     )
 }
+
+// value after the option on the command line
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|n| args.get(n + 1))
+        .map(String::as_str)
+}
+
+// parse the option value
+fn parse_arg<T: std::str::FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    match arg_value(args, name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid value for {name}: {value}")),
+        None => Ok(None),
+    }
+}
+
+// check the translations against the English source
+//
+// flyrust check-i18n [--root <workspace>]  the locale files and their app bundle copy
+// flyrust check-i18n --dir <dir>           a directory of locale files
+fn check_i18n(args: &[String]) -> util::Result<Report> {
+    match arg_value(args, "--dir") {
+        Some(dir) => i18n_check::check_dir(Path::new(dir)),
+        None => i18n_check::check_workspace(Path::new(arg_value(args, "--root").unwrap_or("."))),
+    }
+}
+
+// run rounds with the bot and return the score distributions
+//
+// flyrust simulate [--rounds N] [--seed N] [--reaction TICKS] [--aim-error PX]
+//                  [--speed F] [--difficulty easy|medium|hard]
+//                  [--format csv|json] [--per-round]
+fn simulate(args: &[String]) -> Result<String, String> {
+    let mut config = SimulationConfig::default();
+    if let Some(rounds) = parse_arg(args, "--rounds")? {
+        config.rounds = rounds;
+    }
+    if let Some(seed) = parse_arg(args, "--seed")? {
+        config.seed = seed;
+    }
+    if let Some(reaction) = parse_arg(args, "--reaction")? {
+        config.bot.reaction_ticks = reaction;
+    }
+    if let Some(aim_error) = parse_arg(args, "--aim-error")? {
+        config.bot.aim_error = aim_error;
+    }
+    config.speed = parse_arg(args, "--speed")?;
+    if let Some(difficulty) = arg_value(args, "--difficulty") {
+        config.difficulties = vec![match difficulty {
+            "easy" => Difficulty::Easy,
+            "medium" => Difficulty::Medium,
+            "hard" => Difficulty::Hard,
+            _ => return Err(format!("invalid value for --difficulty: {difficulty}")),
+        }];
+    }
+
+    let json = match arg_value(args, "--format") {
+        None | Some("csv") => false,
+        Some("json") => true,
+        Some(format) => return Err(format!("invalid value for --format: {format}")),
+    };
+
+    let results = simulation::run_batch(&config);
+    let output = if args.iter().any(|arg| arg == "--per-round") {
+        if json {
+            simulation::rounds_to_json(&results)
+        } else {
+            simulation::rounds_to_csv(&results)
+        }
+    } else {
+        let stats = simulation::score_stats(&results);
+        if json {
+            simulation::stats_to_json(&stats)
+        } else {
+            simulation::stats_to_csv(&stats)
+        }
+    };
+    Ok(output)
+}
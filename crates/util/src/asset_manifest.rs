@@ -0,0 +1,220 @@
+//! # asset_manifest
+//!
+//! The list of the game assets, `assets/manifest.json`
+//!
+//! Every texture, sprite sheet, sound and font of the game has an id and a
+//! path relative to `assets`:
+//!
+//! ```json
+//! {
+//!     "textures": [{ "id": "Hand", "path": "image/hand.png" }],
+//!     "sprite_sheets": [
+//!         { "id": "Wings", "path": "image/wings.png", "frame_width": 48, "frame_height": 48 }
+//!     ],
+//!     "sounds": [{ "id": "Catch", "path": "sound/catch.ogg" }],
+//!     "fonts": [{ "id": "UnifontCJK", "path": "fonts/unifont-cjk.ttf" }]
+//! }
+//! ```
+//!
+//! The manifest is checked against the embedded assets: new art only needs
+//! its file in `assets` and an entry here. The files are then read from the
+//! resource roots, so a file on disk can still replace the embedded one.
+use include_dir::Dir;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::assets::ASSETS;
+use crate::error::{Error, Result};
+
+/// The asset manifest, relative to `assets`
+pub const ASSET_MANIFEST: &str = "manifest.json";
+
+// file extensions of each kind of asset
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+const SOUND_EXTENSIONS: &[&str] = &["ogg", "wav", "mp3", "flac"];
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc"];
+
+/// AssetEntry: an asset file and its id
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct AssetEntry {
+    pub id: String,
+    /// Path relative to `assets`
+    pub path: String,
+}
+
+/// SpriteSheetEntry: an image of frames of the same size, row by row
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct SpriteSheetEntry {
+    pub id: String,
+    /// Path relative to `assets`
+    pub path: String,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    /// Number of frames, all the frames of the image by default
+    #[serde(default)]
+    pub frames: Option<u32>,
+}
+
+/// AssetKind: the sections of the manifest
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    Texture,
+    SpriteSheet,
+    Sound,
+    Font,
+}
+
+impl fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetKind::Texture => write!(f, "texture"),
+            AssetKind::SpriteSheet => write!(f, "sprite sheet"),
+            AssetKind::Sound => write!(f, "sound"),
+            AssetKind::Font => write!(f, "font"),
+        }
+    }
+}
+
+impl AssetKind {
+    // file extensions of the kind
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            AssetKind::Texture | AssetKind::SpriteSheet => IMAGE_EXTENSIONS,
+            AssetKind::Sound => SOUND_EXTENSIONS,
+            AssetKind::Font => FONT_EXTENSIONS,
+        }
+    }
+}
+
+/// AssetManifest: the assets of the game, by kind
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct AssetManifest {
+    #[serde(default)]
+    pub textures: Vec<AssetEntry>,
+    #[serde(default)]
+    pub sprite_sheets: Vec<SpriteSheetEntry>,
+    #[serde(default)]
+    pub sounds: Vec<AssetEntry>,
+    #[serde(default)]
+    pub fonts: Vec<AssetEntry>,
+}
+
+impl AssetManifest {
+    /// The manifest embedded in the binary, checked against the embedded assets
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the manifest is missing, not valid, or lists
+    ///   files the assets do not have
+    pub fn embedded() -> Result<Self> {
+        let data = ASSETS
+            .get_file(ASSET_MANIFEST)
+            .and_then(|file| file.contents_utf8())
+            .ok_or_else(|| Error::Asset {
+                path: ASSET_MANIFEST.to_string(),
+                reason: "not found".to_string(),
+            })?;
+        let manifest = Self::from_json(data, ASSET_MANIFEST)?;
+        let issues = manifest.validate(&ASSETS);
+        if !issues.is_empty() {
+            return Err(Error::Asset {
+                path: ASSET_MANIFEST.to_string(),
+                reason: issues.join("; "),
+            });
+        }
+        Ok(manifest)
+    }
+
+    /// Parse a manifest
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the JSON is not a manifest
+    pub fn from_json(data: &str, source: &str) -> Result<Self> {
+        serde_json::from_str(data).map_err(|e| Error::Asset {
+            path: source.to_string(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Check the manifest against a directory of assets
+    ///
+    /// # Returns
+    ///
+    /// * The problems: ids listed twice, files missing from the directory,
+    ///   files of the wrong kind and empty frames
+    ///
+    /// # Examples
+    /// ```
+    /// use util::asset_manifest::AssetManifest;
+    /// use util::assets::ASSETS;
+    ///
+    /// let json = r#"{ "textures": [{ "id": "Hand", "path": "image/hand.png" },
+    ///                              { "id": "Hand", "path": "image/hand.gif" }] }"#;
+    /// let manifest = AssetManifest::from_json(json, "manifest.json").unwrap();
+    /// assert_eq!(
+    ///     manifest.validate(&ASSETS),
+    ///     [
+    ///         "texture Hand is listed twice",
+    ///         "texture Hand: image/hand.gif is not in the assets",
+    ///         "texture Hand: image/hand.gif is not a png, jpg or jpeg file",
+    ///     ]
+    /// );
+    /// ```
+    pub fn validate(&self, dir: &Dir) -> Vec<String> {
+        let mut issues = Vec::new();
+        let mut ids = HashSet::new();
+        for (kind, id, path) in self.entries() {
+            if !ids.insert((kind, id)) {
+                issues.push(format!("{kind} {id} is listed twice"));
+            }
+            if dir.get_file(path).is_none() {
+                issues.push(format!("{kind} {id}: {path} is not in the assets"));
+            }
+            let extension = Path::new(path)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !kind.extensions().contains(&extension.as_str()) {
+                let names = kind.extensions().join(", ");
+                let names = match names.rsplit_once(", ") {
+                    Some((head, last)) => format!("{head} or {last}"),
+                    None => names,
+                };
+                issues.push(format!("{kind} {id}: {path} is not a {names} file"));
+            }
+        }
+        for sheet in &self.sprite_sheets {
+            if sheet.frame_width == 0 || sheet.frame_height == 0 || sheet.frames == Some(0) {
+                issues.push(format!("sprite sheet {} has empty frames", sheet.id));
+            }
+        }
+        issues
+    }
+
+    /// Every entry of the manifest, kind, id and path
+    pub fn entries(&self) -> impl Iterator<Item = (AssetKind, &str, &str)> {
+        entries(AssetKind::Texture, &self.textures)
+            .chain(
+                self.sprite_sheets
+                    .iter()
+                    .map(|s| (AssetKind::SpriteSheet, s.id.as_str(), s.path.as_str())),
+            )
+            .chain(entries(AssetKind::Sound, &self.sounds))
+            .chain(entries(AssetKind::Font, &self.fonts))
+    }
+
+    /// The path of the font of the id
+    pub fn font(&self, id: &str) -> Option<&str> {
+        self.fonts
+            .iter()
+            .find(|font| font.id == id)
+            .map(|font| font.path.as_str())
+    }
+}
+
+// kind, id and path of the entries
+fn entries(kind: AssetKind, list: &[AssetEntry]) -> impl Iterator<Item = (AssetKind, &str, &str)> {
+    list.iter()
+        .map(move |e| (kind, e.id.as_str(), e.path.as_str()))
+}
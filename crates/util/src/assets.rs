@@ -4,33 +4,50 @@
 use eframe::egui;
 use egui::{ColorImage, Context, TextureHandle, TextureOptions};
 use include_dir::{Dir, include_dir};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 use std::fs;
 
-// assets.json path
-pub const I18NUIJSON: &str = "assets/i18n/ui.json";
-pub const I18NUIZHTWJSON: &str = "assets/i18n/ui_zh-TW.json";
-pub const I18NUIZHCNJSON: &str = "assets/i18n/ui_zh-CN.json";
+use crate::asset_manifest::{AssetManifest, SpriteSheetEntry};
+use crate::error::{Error, Result};
+use crate::fluent::ftl_to_value;
+use crate::i18n::{
+    BUILT_IN, DEFAULT_LOCALE, I18nSource, KeyIssue, fallback_chain, parse_locale, resolve_keys,
+};
+use crate::message::{Args, format_message};
+use crate::pseudo::{PSEUDO_LOCALE, pseudo_language, pseudo_value};
+use crate::resource::ResourceLocator;
+use crate::skin::Skin;
 
 pub static ASSETS: Dir = include_dir!("$CARGO_MANIFEST_DIR/../../assets");
-pub static IMAGE_LIST: &[(&str, &str)] = &[
-    // id, path
-    ("Rustacean", "image/rustacean-flat-happy-48x48.png"),
-    ("Hand", "image/hand.png"),
-    ("Beach", "image/wave-sand-beach-800x600.png"),
-];
-
-// Image information
-#[derive(Debug, Deserialize, Clone)]
-pub struct ImageInfo {
-    pub id: String,
-    pub path: String,
-}
 
 // My assets structure
+#[derive(Default)]
 pub struct MyAssets {
     images: HashMap<String, TextureHandle>,
+    sprite_sheets: HashMap<String, SpriteSheet>,
+    sounds: HashMap<String, Vec<u8>>,
+    manifest: AssetManifest,
+}
+
+// Sprite sheet: frames of the same size, row by row
+#[derive(Clone)]
+pub struct SpriteSheet {
+    pub texture: TextureHandle,
+    pub frames: usize,
+    columns: usize,
+    rows: usize,
+}
+
+impl SpriteSheet {
+    /// uv rectangle of the frame in the texture, the frames loop
+    pub fn uv(&self, frame: usize) -> egui::Rect {
+        let frame = frame % self.frames.max(1);
+        let (w, h) = (1.0 / self.columns as f32, 1.0 / self.rows as f32);
+        let (x, y) = ((frame % self.columns) as f32, (frame / self.columns) as f32);
+        egui::Rect::from_min_size(egui::pos2(x * w, y * h), egui::vec2(w, h))
+    }
 }
 
 impl MyAssets {
@@ -39,76 +56,205 @@ impl MyAssets {
         self.images.get(id)
     }
 
-    /// load all image from
-    pub fn load_from_json(ctx: &Context) -> Self {
+    /// get sprite sheet
+    pub fn sprite_sheet(&self, id: &str) -> Option<&SpriteSheet> {
+        self.sprite_sheets.get(id)
+    }
+
+    /// get the encoded sound
+    pub fn sound(&self, id: &str) -> Option<&[u8]> {
+        self.sounds.get(id).map(Vec::as_slice)
+    }
+
+    /// load the assets of the embedded asset manifest
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the manifest is not valid, or an asset is
+    ///   missing or cannot be decoded
+    pub fn load_from_json(ctx: &Context) -> Result<Self> {
         // install image loader
         egui_extras::install_image_loaders(ctx);
-        // load assets inside the code
+        Self::load_manifest(ctx, &AssetManifest::embedded()?)
+    }
 
-        let mut images = HashMap::new();
+    /// load the textures, sprite sheets and sounds of a manifest
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if an asset is missing or cannot be decoded
+    pub fn load_manifest(ctx: &Context, manifest: &AssetManifest) -> Result<Self> {
+        let mut assets = Self {
+            manifest: manifest.clone(),
+            ..Self::default()
+        };
+        for texture in &manifest.textures {
+            let tex = Self::load_texture(ctx, &texture.id, &texture.path)?;
+            assets.images.insert(texture.id.clone(), tex);
+        }
+        for sheet in &manifest.sprite_sheets {
+            let texture = Self::load_texture(ctx, &sheet.id, &sheet.path)?;
+            let sprite_sheet = Self::cut_sprite_sheet(sheet, &sheet.path, texture)?;
+            assets.sprite_sheets.insert(sheet.id.clone(), sprite_sheet);
+        }
+        for sound in &manifest.sounds {
+            let data = Self::read_asset(&sound.path)?;
+            assets.sounds.insert(sound.id.clone(), data);
+        }
+        Ok(assets)
+    }
 
-        // for img in image_list {
-        for (id, path) in IMAGE_LIST {
-            let tex = Self::load_texture(ctx, id, path);
-            images.insert(id.to_string(), tex);
+    /// replace the textures and sprite sheets of the manifest by the ones of the skin
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the skin overrides an id the manifest does
+    ///   not have, or an image of the skin cannot be decoded; the assets are
+    ///   left as they were
+    pub fn apply_skin(&mut self, ctx: &Context, skin: &Skin) -> Result<()> {
+        let mut images = Vec::new();
+        let mut sprite_sheets = Vec::new();
+        for (id, file) in &skin.textures {
+            let path = format!("{}: {file}", skin.path.display());
+            let is_texture = self.manifest.textures.iter().any(|t| &t.id == id);
+            let sheet = self.manifest.sprite_sheets.iter().find(|s| &s.id == id);
+            if !is_texture && sheet.is_none() {
+                return Err(Error::Asset {
+                    path,
+                    reason: format!("{id} is not a texture of the asset manifest"),
+                });
+            }
+            let texture = Self::decode_texture(ctx, id, &path, &skin.read(file)?)?;
+            match sheet {
+                Some(sheet) => {
+                    sprite_sheets.push((id, Self::cut_sprite_sheet(sheet, &path, texture)?));
+                }
+                None => images.push((id, texture)),
+            }
+        }
+        for (id, texture) in images {
+            self.images.insert(id.clone(), texture);
+        }
+        for (id, sprite_sheet) in sprite_sheets {
+            self.sprite_sheets.insert(id.clone(), sprite_sheet);
         }
+        Ok(())
+    }
 
-        Self { images }
+    // the frames of the sheet entry in the texture
+    fn cut_sprite_sheet(
+        sheet: &SpriteSheetEntry,
+        path: &str,
+        texture: TextureHandle,
+    ) -> Result<SpriteSheet> {
+        let asset_error = |reason: String| Error::Asset {
+            path: path.to_string(),
+            reason,
+        };
+        let [width, height] = texture.size();
+        let (frame_width, frame_height) = (sheet.frame_width as usize, sheet.frame_height as usize);
+        if frame_width == 0
+            || frame_height == 0
+            || width % frame_width != 0
+            || height % frame_height != 0
+        {
+            return Err(asset_error(format!(
+                "{width}x{height} is not made of {frame_width}x{frame_height} frames"
+            )));
+        }
+        let (columns, rows) = (width / frame_width, height / frame_height);
+        let frames = sheet.frames.map_or(columns * rows, |n| n as usize);
+        if frames > columns * rows {
+            return Err(asset_error(format!(
+                "has {} frames, not {frames}",
+                columns * rows
+            )));
+        }
+        Ok(SpriteSheet {
+            texture,
+            frames,
+            columns,
+            rows,
+        })
     }
 
     /// load texture
-    fn load_texture(ctx: &Context, id: &str, path: &str) -> TextureHandle {
-        let data = ASSETS
-            .get_file(path)
-            .unwrap_or_else(|| panic!("Asset not found: {}", path))
-            .contents();
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the image is missing or cannot be decoded
+    pub fn load_texture(ctx: &Context, id: &str, path: &str) -> Result<TextureHandle> {
+        Self::decode_texture(ctx, id, path, &Self::read_asset(path)?)
+    }
+
+    // the texture of the encoded image
+    fn decode_texture(ctx: &Context, id: &str, path: &str, data: &[u8]) -> Result<TextureHandle> {
+        let asset_error = |reason: String| Error::Asset {
+            path: path.to_string(),
+            reason,
+        };
         let image = image::load_from_memory(data)
-            .expect(&format!("Failed to open image: {}", path))
+            .map_err(|e| asset_error(e.to_string()))?
             .to_rgba8();
 
         let size = [image.width() as usize, image.height() as usize];
         let pixels = image.into_raw();
         let color_image = ColorImage::from_rgba_unmultiplied(size, &pixels);
 
-        ctx.load_texture(id, color_image, TextureOptions::LINEAR)
+        Ok(ctx.load_texture(id, color_image, TextureOptions::LINEAR))
+    }
+
+    // resource roots on disk can replace the embedded asset
+    fn read_asset(path: &str) -> Result<Vec<u8>> {
+        let asset_error = |reason: String| Error::Asset {
+            path: path.to_string(),
+            reason,
+        };
+        ResourceLocator::new()
+            .locate(&format!("assets/{path}"))
+            .ok_or_else(|| asset_error("not found".to_string()))?
+            .read()
+            .map_err(|e| asset_error(e.to_string()))
     }
 }
 
 // language
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct LanguageItem {
     pub code: String,
     pub name: String,
+    /// Locale the missing keys fall back to, before the default locale
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct UiLabels {
     pub dashboard: String,
     pub start: String,
     pub pause: String,
     pub stop: String,
     pub difficulty: String,
+    /// Hint of the skin selector
+    pub skin: String,
+    /// Name of the look without a skin pack
+    pub default_skin: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct DifficultyLabels {
     pub easy: String,
     pub medium: String,
     pub hard: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct PopupLabels {
     pub game_over: String,
     pub ok: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct GameOver {
     pub title: String,
     pub ok: String,
-    pub error: String,
-    pub warning: String,
+    /// Message of the score, `{score}` is plural
     pub info: String,
     pub close_title: String,
     pub close_ok: String,
@@ -117,24 +263,183 @@ pub struct GameOver {
     pub ranking: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StorageLabels {
+    pub session_only: String,
+    pub retry: String,
+    pub export: String,
+    pub exported: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct UiConfig {
-    pub languages: Vec<LanguageItem>,
+    pub locale: LanguageItem,
     pub app_name: String,
     pub labels: UiLabels,
     pub difficulty: DifficultyLabels,
     pub popup: PopupLabels,
     pub gameover: GameOver,
+    pub storage: StorageLabels,
 }
 
 impl UiConfig {
-    pub fn load(path: &str) -> Self {
-        let data = fs::read_to_string(path).unwrap_or_else(|_| "{}".to_string());
-        serde_json::from_str(&data).unwrap_or_else(|_| UiConfig {
-            languages: vec![LanguageItem {
+    /// Load the translation file, JSON or Fluent by its extension
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if the file cannot be read or parsed
+    pub fn load(path: &str) -> Result<Self> {
+        let i18n_error = |reason: String| Error::I18n {
+            path: path.to_string(),
+            reason,
+        };
+        let data = fs::read_to_string(path).map_err(|e| i18n_error(e.to_string()))?;
+        let (layer, issues) = parse_locale(path, &data, path)?;
+        Self::resolve_logged(&[(path.to_string(), layer)], issues)
+    }
+
+    /// Load the locale of the code, embedded or from the translator override directory
+    ///
+    /// Keys missing from the locale resolve through its fallback chain, like
+    /// zh-TW → zh-CN → en, then the built-in English labels. Missing and
+    /// unknown keys are logged. The pseudo-locale is made from English.
+    ///
+    /// # Returns
+    ///
+    /// * The labels and where the file of the locale came from
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if no locale has the code, or its file cannot be read or parsed
+    pub fn load_locale(code: &str) -> Result<(Self, I18nSource)> {
+        if code == PSEUDO_LOCALE {
+            let (english, _) = Self::load_locale(DEFAULT_LOCALE)?;
+            return Ok((english.pseudo(), I18nSource::Generated));
+        }
+        let chain = fallback_chain(code);
+        let Some(first) = chain.first() else {
+            return Err(Error::I18n {
+                path: code.to_string(),
+                reason: "no locale file declares the code".to_string(),
+            });
+        };
+        let source = first.source.clone();
+
+        let (layer, mut issues) = first.load()?;
+        let mut layers = vec![(code.to_string(), layer)];
+        // a broken fallback file only loses its own keys
+        for info in &chain[1..] {
+            match info.load() {
+                Ok((layer, layer_issues)) => {
+                    layers.push((info.language.code.clone(), layer));
+                    issues.extend(layer_issues);
+                }
+                Err(e) => log::warn!("{e}"),
+            }
+        }
+        Ok((Self::resolve_logged(&layers, issues)?, source))
+    }
+
+    /// Parse the translation JSON, `source` names it in errors
+    ///
+    /// Missing keys take the built-in English labels, missing and unknown
+    /// keys are logged.
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if the JSON cannot be parsed
+    pub fn from_json(data: &str, source: &str) -> Result<Self> {
+        let layer = serde_json::from_str(data).map_err(|e| Error::I18n {
+            path: source.to_string(),
+            reason: e.to_string(),
+        })?;
+        Self::resolve_logged(&[(source.to_string(), layer)], Vec::new())
+    }
+
+    /// Parse the Fluent translation, `source` names it in errors
+    ///
+    /// Missing keys take the built-in English labels, missing and unknown
+    /// keys and messages that cannot be converted are logged.
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if the Fluent file has syntax errors
+    pub fn from_ftl(data: &str, source: &str) -> Result<Self> {
+        let (layer, issues) = ftl_to_value(data, source)?;
+        Self::resolve_logged(&[(source.to_string(), layer)], issues)
+    }
+
+    /// Resolve the labels through the named translation layers, first one first
+    ///
+    /// # Returns
+    ///
+    /// * The labels and the keys that did not resolve as they are
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if the `locale` header of the first layer is broken
+    pub fn resolve(layers: &[(String, Value)]) -> Result<(Self, Vec<KeyIssue>)> {
+        let template = serde_json::to_value(Self::default()).map_err(|e| Error::I18n {
+            path: BUILT_IN.to_string(),
+            reason: e.to_string(),
+        })?;
+        let (value, issues) = resolve_keys(&template, layers);
+        let ui = serde_json::from_value(value).map_err(|e| Error::I18n {
+            path: layers
+                .first()
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+            reason: e.to_string(),
+        })?;
+        Ok((ui, issues))
+    }
+
+    /// The pseudo-localized labels, accented and padded
+    ///
+    /// # Examples
+    /// ```
+    /// use util::assets::UiConfig;
+    /// use util::pseudo::PSEUDO_LOCALE;
+    /// let pseudo = UiConfig::default().pseudo();
+    /// assert_eq!(pseudo.locale.code, PSEUDO_LOCALE);
+    /// assert_eq!(pseudo.labels.start, "[Šţáŕţ ~~]");
+    /// ```
+    pub fn pseudo(&self) -> Self {
+        let labels = serde_json::to_value(self).map(|value| pseudo_value(&value));
+        let mut ui: Self = labels.and_then(serde_json::from_value).unwrap_or_default();
+        ui.locale = pseudo_language();
+        ui
+    }
+
+    /// Fill the message with the arguments, in the formats of the locale
+    ///
+    /// # Examples
+    /// ```
+    /// use util::assets::UiConfig;
+    /// use util::message::Args;
+    /// let ui = UiConfig::default();
+    /// let args = Args::new().with("score", 0);
+    /// assert_eq!(ui.format(&ui.gameover.info, &args), "BAD! YOU GOT NOTHING!");
+    /// ```
+    pub fn format(&self, template: &str, args: &Args) -> String {
+        format_message(&self.locale.code, template, args)
+    }
+
+    // resolve the labels and log the keys that did not resolve as they are
+    fn resolve_logged(layers: &[(String, Value)], mut issues: Vec<KeyIssue>) -> Result<Self> {
+        let (ui, resolve_issues) = Self::resolve(layers)?;
+        issues.extend(resolve_issues);
+        for issue in &issues {
+            log::warn!("{issue}");
+        }
+        Ok(ui)
+    }
+}
+
+impl Default for UiConfig {
+    /// English labels, used when no translation file can be loaded
+    fn default() -> Self {
+        UiConfig {
+            locale: LanguageItem {
                 code: "en".to_string(),
                 name: "English".to_string(),
-            }],
+                fallback: None,
+            },
             app_name: "APPName".to_string(),
             labels: UiLabels {
                 dashboard: "Dashboard".to_string(),
@@ -142,6 +447,8 @@ impl UiConfig {
                 pause: "Pause".to_string(),
                 stop: "Stop".to_string(),
                 difficulty: "Difficulty".to_string(),
+                skin: "Skin".to_string(),
+                default_skin: "Classic".to_string(),
             },
             difficulty: DifficultyLabels {
                 easy: "⭐".to_string(),
@@ -155,15 +462,22 @@ impl UiConfig {
             gameover: GameOver {
                 title: "Game Over".to_string(),
                 ok: "OK".to_string(),
-                error: "BAD! YOU GOT NOTHING!".to_string(),
-                warning: "OOPS, you got ONLY 1.".to_string(),
-                info: "You got {} Rustaceans.".to_string(),
+                info: "{score, plural, =0 {BAD! YOU GOT NOTHING!} one {OOPS, you got ONLY #.} \
+                       other {You got # Rustaceans.}}"
+                    .to_string(),
                 close_title: "ByeBye".to_string(),
                 close_ok: "ByeBye".to_string(),
                 close_bye: "Have a good day.\nSee you soon.".to_string(),
                 player: "Name".to_string(),
                 ranking: "Have Fun".to_string(),
             },
-        })
+            storage: StorageLabels {
+                session_only: "Scores are kept for this session only.".to_string(),
+                retry: "Retry".to_string(),
+                export: "Export".to_string(),
+                exported: "Exported {count, plural, one {# score} other {# scores}} to {path}"
+                    .to_string(),
+            },
+        }
     }
 }
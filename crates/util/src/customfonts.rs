@@ -1,56 +1,373 @@
-//! customfonts
-//! Set the font
+//! # customfonts
+//!
+//! Set the fonts of each locale from the font manifest
+//!
+//! `assets/fonts/fonts.json` lists the font faces and, for each locale, the
+//! faces to look for a glyph in, in order:
+//!
+//! ```json
+//! {
+//!     "faces": [
+//!         { "id": "Ubuntu-Light" },
+//!         { "id": "SystemCJK_TC", "system": ["PingFang TC", "Noto Sans CJK TC"] },
+//!         { "id": "UnifontCJK", "file": "fonts/unifont-cjk.ttf" }
+//!     ],
+//!     "monospace": ["Hack"],
+//!     "locales": { "en": ["Ubuntu-Light"], "zh-TW": ["SystemCJK_TC", "Ubuntu-Light", "UnifontCJK"] }
+//! }
+//! ```
+//!
+//! A face is its `file`, relative to `assets`, or the font of the asset
+//! manifest with its id, looked up in the resource roots and the embedded
+//! assets, else the first installed `system` family, else the egui font of
+//! that id. A locale without a chain uses the chain of its translation
+//! fallback, and English at last.
+//!
+//! `SystemCJK_SC` and `SystemCJK_TC` are the installed Simplified and
+//! Traditional Chinese fonts, no file of them is bundled. The only bundled
+//! CJK face is `fonts/unifont-cjk.ttf`, a subset of GNU Unifont to the
+//! characters of the translations made by `font_subset.py`: without a
+//! system CJK font, zh-CN and zh-TW both fall back to it.
 use eframe::egui;
 use font_kit::family_name::FamilyName;
+use font_kit::handle::Handle;
 use font_kit::properties::Properties;
 use font_kit::source::SystemSource;
-/// Set the font to use the system fonts
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::sync::Arc;
+
+use crate::asset_manifest::AssetManifest;
+use crate::error::{Error, Result};
+use crate::i18n::{DEFAULT_LOCALE, fallback_chain};
+use crate::resource::ResourceLocator;
+
+/// The font manifest, relative to the resource root
+pub const FONT_MANIFEST: &str = "assets/fonts/fonts.json";
+
+/// FontFace: a font of the manifest
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct FontFace {
+    pub id: String,
+    /// Path relative to `assets`
+    #[serde(default)]
+    pub file: Option<String>,
+    /// Installed families to use when the file is not there
+    #[serde(default)]
+    pub system: Vec<String>,
+}
+
+/// FontManifest: the faces and the fallback chain of each locale
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct FontManifest {
+    pub faces: Vec<FontFace>,
+    /// Faces put before the locale chain in the monospace family
+    #[serde(default)]
+    pub monospace: Vec<String>,
+    /// Chain of face ids, by locale code
+    pub locales: BTreeMap<String, Vec<String>>,
+}
+
+impl FontManifest {
+    /// Parse a font manifest, every id of a chain must be a face
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the manifest is not valid
+    ///
+    /// # Examples
+    /// ```
+    /// use util::customfonts::FontManifest;
+    /// let json = r#"{ "faces": [{ "id": "Hack" }], "locales": { "en": ["Hack"] } }"#;
+    /// let manifest = FontManifest::from_json(json, "fonts.json").unwrap();
+    /// assert_eq!(manifest.locales["en"], ["Hack"]);
+    /// assert!(FontManifest::from_json(r#"{ "faces": [], "locales": { "en": ["Hack"] } }"#, "fonts.json").is_err());
+    /// ```
+    pub fn from_json(data: &str, source: &str) -> Result<Self> {
+        let asset_error = |reason: String| Error::Asset {
+            path: source.to_string(),
+            reason,
+        };
+        let manifest: Self = serde_json::from_str(data).map_err(|e| asset_error(e.to_string()))?;
+        let ids: BTreeSet<&str> = manifest.faces.iter().map(|f| f.id.as_str()).collect();
+        let chains = manifest
+            .locales
+            .iter()
+            .map(|(code, chain)| (code.as_str(), chain))
+            .chain([("monospace", &manifest.monospace)]);
+        for (name, chain) in chains {
+            if let Some(id) = chain.iter().find(|id| !ids.contains(id.as_str())) {
+                return Err(asset_error(format!("{name} uses the unknown face {id}")));
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+// a face that could be loaded
+struct LoadedFace {
+    data: Arc<egui::FontData>,
+    origin: String,
+}
+
+/// FontLibrary: the faces of the manifest that could be loaded
+#[derive(Default)]
+pub struct FontLibrary {
+    manifest: FontManifest,
+    faces: BTreeMap<String, LoadedFace>,
+    unavailable: Vec<(String, String)>,
+    skin_faces: Vec<String>,
+}
+
+impl FontLibrary {
+    /// Load the faces of the font manifest of the resource roots
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the manifest is missing or not valid
+    pub fn load() -> Result<Self> {
+        let asset_error = |reason: String| Error::Asset {
+            path: FONT_MANIFEST.to_string(),
+            reason,
+        };
+        let data = ResourceLocator::new()
+            .require(FONT_MANIFEST)
+            .and_then(|located| located.read_to_string())
+            .map_err(|e| asset_error(e.to_string()))?;
+        let mut manifest = FontManifest::from_json(&data, FONT_MANIFEST)?;
+        // the bundled fonts of the asset manifest
+        if let Ok(assets) = AssetManifest::embedded() {
+            for face in manifest.faces.iter_mut().filter(|face| face.file.is_none()) {
+                face.file = assets.font(&face.id).map(str::to_string);
+            }
+        }
+        Ok(Self::from_manifest(manifest))
+    }
+
+    /// Load the faces of a manifest, a face that cannot be loaded is logged and left out
+    pub fn from_manifest(manifest: FontManifest) -> Self {
+        let builtin = egui::FontDefinitions::default().font_data;
+        let mut faces = BTreeMap::new();
+        let mut unavailable = Vec::new();
+        for face in &manifest.faces {
+            match load_face(face, &builtin) {
+                Ok(loaded) => {
+                    log::debug!("font {}: {}", face.id, loaded.origin);
+                    faces.insert(face.id.clone(), loaded);
+                }
+                Err(reason) => {
+                    log::warn!("font {}: {reason}", face.id);
+                    unavailable.push((face.id.clone(), reason));
+                }
+            }
+        }
+        Self {
+            manifest,
+            faces,
+            unavailable,
+            skin_faces: Vec::new(),
+        }
+    }
+
+    /// Put the fonts of a skin before the chain of every locale, in place of
+    /// the ones of the last skin
+    ///
+    /// # Arguments
+    ///
+    /// * `fonts`: &[(String, Vec<u8>)] - name and content of the font files
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if a file is not a font, the fonts are left as they were
+    pub fn set_skin_fonts(&mut self, fonts: &[(String, Vec<u8>)]) -> Result<()> {
+        let mut loaded = Vec::new();
+        for (name, bytes) in fonts {
+            let data = font_data(bytes.clone(), 0).map_err(|reason| Error::Asset {
+                path: name.clone(),
+                reason,
+            })?;
+            let origin = format!("skin {name}");
+            loaded.push((format!("skin:{name}"), LoadedFace { data, origin }));
+        }
+        for id in self.skin_faces.drain(..) {
+            self.faces.remove(&id);
+        }
+        for (id, face) in loaded {
+            self.skin_faces.push(id.clone());
+            self.faces.insert(id, face);
+        }
+        Ok(())
+    }
+
+    /// The faces that could not be loaded, with the reason
+    pub fn unavailable(&self) -> &[(String, String)] {
+        &self.unavailable
+    }
+
+    /// Where the face was loaded from
+    pub fn origin(&self, id: &str) -> Option<&str> {
+        self.faces.get(id).map(|face| face.origin.as_str())
+    }
+
+    /// The loaded faces of the locale chain, in order, after the skin fonts
+    ///
+    /// The chain is the one of the locale, else of its translation fallback,
+    /// else of English.
+    pub fn chain(&self, locale: &str) -> Vec<String> {
+        let codes = std::iter::once(locale.to_string())
+            .chain(fallback_chain(locale).into_iter().map(|l| l.language.code))
+            .chain([DEFAULT_LOCALE.to_string()]);
+        let chain = codes
+            .filter_map(|code| self.manifest.locales.get(&code))
+            .next()
+            .map(|chain| self.loaded(chain))
+            .unwrap_or_default();
+        self.skin_faces.iter().cloned().chain(chain).collect()
+    }
+
+    /// The egui fonts of the locale, the egui defaults without a chain
+    pub fn definitions(&self, locale: &str) -> egui::FontDefinitions {
+        let mut fonts = egui::FontDefinitions::default();
+        let chain = self.chain(locale);
+        if chain.is_empty() {
+            return fonts;
+        }
+        for (id, face) in &self.faces {
+            fonts.font_data.insert(id.clone(), face.data.clone());
+        }
+        let mut monospace = self.loaded(&self.manifest.monospace);
+        for id in &chain {
+            if !monospace.contains(id) {
+                monospace.push(id.clone());
+            }
+        }
+        fonts.families.insert(egui::FontFamily::Proportional, chain);
+        if !monospace.is_empty() {
+            fonts
+                .families
+                .insert(egui::FontFamily::Monospace, monospace);
+        }
+        fonts
+    }
+
+    /// Set the fonts of the locale
+    pub fn apply(&self, ctx: &egui::Context, locale: &str) {
+        log::info!("fonts of {locale}: {}", self.chain(locale).join(", "));
+        ctx.set_fonts(self.definitions(locale));
+    }
+
+    /// The characters of the text no face of the locale chain has a glyph for
+    ///
+    /// # Examples
+    /// ```
+    /// use util::customfonts::{FontLibrary, FontManifest};
+    /// let json = r#"{ "faces": [{ "id": "Ubuntu-Light" }], "locales": { "en": ["Ubuntu-Light"] } }"#;
+    /// let fonts = FontLibrary::from_manifest(FontManifest::from_json(json, "fonts.json").unwrap());
+    /// assert_eq!(fonts.missing_glyphs("en", "Fly 蒼蠅"), ['蒼', '蠅']);
+    /// ```
+    pub fn missing_glyphs(&self, locale: &str, text: &str) -> Vec<char> {
+        let faces: Vec<ttf_parser::Face<'_>> = self
+            .chain(locale)
+            .iter()
+            .filter_map(|id| self.faces.get(id))
+            .filter_map(|face| ttf_parser::Face::parse(&face.data.font, face.data.index).ok())
+            .collect();
+        let missing: BTreeSet<char> = text
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .filter(|c| !faces.iter().any(|face| face.glyph_index(*c).is_some()))
+            .collect();
+        missing.into_iter().collect()
+    }
+
+    // the loaded faces of the ids
+    fn loaded(&self, ids: &[String]) -> Vec<String> {
+        ids.iter()
+            .filter(|id| self.faces.contains_key(*id))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Load the font manifest and set the fonts of the locale
+///
 /// # Arguments
 ///
 /// * `ctx`: &egui::Context - egui Context
+/// * `locale`: &str - code of the locale
+///
+/// # Errors
+/// - Returns `Error::Asset` if the font manifest is missing or not valid,
+///   the egui fonts are left as they are
 ///
 /// # Examples
 /// ```rust,no_run
-///     setup_custom_fonts(&cc.egui_ctx);
+/// # use util::i18n::DEFAULT_LOCALE;
+/// # use util::setup_custom_fonts;
+/// # fn main() -> util::Result<()> {
+/// # let ctx = eframe::egui::Context::default();
+///     let fonts = setup_custom_fonts(&ctx, DEFAULT_LOCALE)?;
+/// # Ok(())
+/// # }
 /// ```
 ///
-pub fn setup_custom_fonts(ctx: &egui::Context) {
-    let mut fonts = egui::FontDefinitions::default();
-
-    let source = SystemSource::new();
-    let family_names = [
-        FamilyName::Title("PingFang TC".to_string()),
-        FamilyName::Title("Microsoft JhengHei".to_string()),
-        FamilyName::SansSerif,
-    ];
-
-    if let Ok(handle) = source.select_best_match(&family_names, &Properties::new()) {
-        if let Ok(font_data) = handle.load() {
-            if let Some(font_arc) = font_data.copy_font_data() {
-                let font_vec = font_arc.as_ref().clone();
-
-                // inter font
-                fonts.font_data.insert(
-                    "sys_cjk".to_owned(),
-                    egui::FontData::from_owned(font_vec).into(),
-                );
-
-                // set font families
-                fonts
-                    .families
-                    .get_mut(&egui::FontFamily::Proportional)
-                    .unwrap()
-                    .insert(0, "sys_cjk".to_owned());
-
-                // set mono font for backup fonts
-                let mono = fonts
-                    .families
-                    .get_mut(&egui::FontFamily::Monospace)
-                    .unwrap();
-                mono.push("sys_cjk".to_owned());
-            }
+pub fn setup_custom_fonts(ctx: &egui::Context, locale: &str) -> Result<FontLibrary> {
+    let fonts = FontLibrary::load()?;
+    fonts.apply(ctx, locale);
+    Ok(fonts)
+}
+
+// the file, else a system family, else the egui font of the id
+fn load_face(
+    face: &FontFace,
+    builtin: &BTreeMap<String, Arc<egui::FontData>>,
+) -> std::result::Result<LoadedFace, String> {
+    let mut tried = Vec::new();
+    if let Some(file) = &face.file {
+        let path = format!("assets/{file}");
+        if let Some(located) = ResourceLocator::new().locate(&path) {
+            let bytes = located.read().map_err(|e| e.to_string())?;
+            return font_data(bytes, 0).map(|data| LoadedFace {
+                data,
+                origin: format!("{path} in {}", located.root),
+            });
         }
+        tried.push(path);
     }
+    for family in &face.system {
+        if let Some((bytes, index)) = system_font(family) {
+            return font_data(bytes, index).map(|data| LoadedFace {
+                data,
+                origin: format!("system family {family}"),
+            });
+        }
+        tried.push(format!("system family {family}"));
+    }
+    if let Some(data) = builtin.get(&face.id) {
+        return Ok(LoadedFace {
+            data: data.clone(),
+            origin: "egui".to_string(),
+        });
+    }
+    if tried.is_empty() {
+        return Err("no file, system family or egui font".to_string());
+    }
+    Err(format!("not found, tried {}", tried.join(", ")))
+}
+
+// an installed family, the font file and the index of the face in it
+fn system_font(family: &str) -> Option<(Vec<u8>, u32)> {
+    let handle = SystemSource::new()
+        .select_best_match(&[FamilyName::Title(family.to_string())], &Properties::new())
+        .ok()?;
+    match handle {
+        Handle::Path { path, font_index } => Some((fs::read(path).ok()?, font_index)),
+        Handle::Memory { bytes, font_index } => Some((bytes.as_ref().clone(), font_index)),
+    }
+}
 
-    ctx.set_fonts(fonts);
+// egui panics on a font it cannot parse, so it is checked first
+fn font_data(bytes: Vec<u8>, index: u32) -> std::result::Result<Arc<egui::FontData>, String> {
+    ttf_parser::Face::parse(&bytes, index).map_err(|e| format!("not a font: {e}"))?;
+    let mut data = egui::FontData::from_owned(bytes);
+    data.index = index;
+    Ok(Arc::new(data))
 }
@@ -0,0 +1,124 @@
+//! # data_dir
+//!
+//! Where the game keeps its data: the score database and replays
+//!
+//! The data directory is, in order:
+//! 1. the directory given on the command line with `set_data_dir`
+//! 2. the `FLYRUST_DATA_DIR` environment variable
+//! 3. the platform data directory, `$XDG_DATA_HOME/flyrust` or
+//!    `~/.local/share/flyrust` on Linux, `~/Library/Application Support/FlyRust`
+//!    on macOS and `%APPDATA%\FlyRust` on Windows
+//! 4. the directory next to the app, where older versions kept the data
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable overriding the data directory
+pub const DATA_DIR_ENV: &str = "FLYRUST_DATA_DIR";
+
+/// Data directory given on the command line
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Override the data directory, from the command line
+///
+/// Call it before the data is used, only the first call counts.
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = DATA_DIR_OVERRIDE.set(dir);
+}
+
+/// The data directory of the game
+pub fn data_dir() -> PathBuf {
+    resolve_data_dir(
+        DATA_DIR_OVERRIDE.get().map(PathBuf::as_path),
+        env::var_os(DATA_DIR_ENV),
+        platform_data_dir(),
+    )
+}
+
+/// Pick the data directory from the override, the environment and the platform
+///
+/// # Examples
+/// ```
+/// use std::path::{Path, PathBuf};
+/// use util::data_dir::resolve_data_dir;
+///
+/// let platform = Some(PathBuf::from("/home/ferris/.local/share/flyrust"));
+/// assert_eq!(
+///     resolve_data_dir(None, Some("/tmp/fly".into()), platform.clone()),
+///     PathBuf::from("/tmp/fly")
+/// );
+/// assert_eq!(
+///     resolve_data_dir(Some(Path::new("/data")), Some("/tmp/fly".into()), platform),
+///     PathBuf::from("/data")
+/// );
+/// ```
+pub fn resolve_data_dir(
+    cli: Option<&Path>,
+    env: Option<OsString>,
+    platform: Option<PathBuf>,
+) -> PathBuf {
+    if let Some(dir) = cli {
+        return dir.to_path_buf();
+    }
+    if let Some(dir) = env.filter(|dir| !dir.is_empty()) {
+        return PathBuf::from(dir);
+    }
+    platform
+        .or_else(legacy_data_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The platform data directory of the game
+pub fn platform_data_dir() -> Option<PathBuf> {
+    let name = if cfg!(target_os = "linux") {
+        "flyrust"
+    } else {
+        "FlyRust"
+    };
+    dirs::data_dir().map(|dir| dir.join(name))
+}
+
+/// Where to export data the data directory cannot take, outside of it: the
+/// documents, home and temporary directories
+pub fn export_dirs() -> Vec<PathBuf> {
+    dirs::document_dir()
+        .into_iter()
+        .chain(dirs::home_dir())
+        .chain([env::temp_dir()])
+        .collect()
+}
+
+/// The directory older versions kept the data in, next to `FlyRust.app`
+pub fn legacy_data_dir() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    exe.parent() // MacOS
+        .and_then(|p| p.parent()) // Contents
+        .and_then(|p| p.parent()) // FlyRust.app
+        .and_then(|p| p.parent()) // dist
+        .map(Path::to_path_buf)
+}
+
+/// Copy a data file from where older versions kept it
+///
+/// Nothing is copied when the file is already at the new place, the old
+/// file is left as it is.
+///
+/// # Returns
+///
+/// * true when the file was copied
+///
+/// # Errors
+/// - Returns an error if the new directory or the file cannot be written
+pub fn migrate_legacy_file(old: &Path, new: &Path) -> io::Result<bool> {
+    if new.exists() || !old.is_file() || old == new {
+        return Ok(false);
+    }
+    if let Some(dir) = new.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::copy(old, new)?;
+    Ok(true)
+}
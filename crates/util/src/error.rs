@@ -0,0 +1,58 @@
+//! # error
+//!
+//! Errors of the util crate
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::score::StorageError;
+
+/// Error: what can go wrong in util
+#[derive(Debug)]
+pub enum Error {
+    /// The score storage failed
+    Storage(StorageError),
+    /// An embedded asset is missing or cannot be decoded
+    Asset { path: String, reason: String },
+    /// A translation file cannot be read or parsed
+    I18n { path: String, reason: String },
+    /// A message template is not valid
+    Message { message: String, reason: String },
+    /// A file system path cannot be resolved
+    Path { path: PathBuf, reason: String },
+}
+
+/// Result of util
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Storage(e) => write!(f, "{e}"),
+            Error::Asset { path, reason } => write!(f, "asset {path}: {reason}"),
+            Error::I18n { path, reason } => write!(f, "translation {path}: {reason}"),
+            Error::Message { message, reason } => write!(f, "message {message:?}: {reason}"),
+            Error::Path { path, reason } => write!(f, "path {}: {reason}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Storage(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<StorageError> for Error {
+    fn from(e: StorageError) -> Self {
+        Error::Storage(e)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Storage(StorageError::Sqlite(e))
+    }
+}
@@ -0,0 +1,319 @@
+//! # fluent
+//!
+//! Read the translation files of Project Fluent
+//!
+//! A Fluent file gives the same labels as a JSON locale file. A message with
+//! a value is a label of the top level, a message with attributes a group of
+//! labels:
+//!
+//! ```ftl
+//! locale =
+//!     .code = fr
+//!     .name = Français
+//! app_name = Mouche Rust
+//! gameover =
+//!     .title = Fin de partie
+//!     .info = { $score ->
+//!         [0] RIEN !
+//!         [one] Vous avez { $score } Rustacean.
+//!        *[other] Vous avez { $score } Rustaceans.
+//!     }
+//! ```
+//!
+//! The placeables become the syntax of the `message` module: variables are
+//! arguments, `NUMBER()` is a number, a selector with number keys or plural
+//! category keys is a plural and any other selector a select. Referenced
+//! messages and terms are inlined.
+use fluent_syntax::ast::{
+    CallArguments, Entry, Expression, InlineExpression, Pattern, PatternElement, Variant,
+    VariantKey,
+};
+use fluent_syntax::parser;
+use fluent_syntax::unicode::unescape_unicode_to_string;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::i18n::KeyIssue;
+use crate::message::quote;
+
+// CLDR plural categories, the variant keys of a plural
+const PLURAL_CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+// references inlined in one another at most
+const MAX_DEPTH: usize = 8;
+
+/// Convert a Fluent file to the labels of a JSON locale file
+///
+/// # Returns
+///
+/// * The labels, and the messages that could not be converted
+///
+/// # Errors
+/// - Returns `Error::I18n` if the file has syntax errors
+///
+/// # Examples
+/// ```
+/// use util::fluent::ftl_to_value;
+/// let ftl = "popup =\n    .ok = D'accord\n    .game_over = { $n -> \n        [one] # perdu\n       *[other] Perdu\n    }\n";
+/// let (value, issues) = ftl_to_value(ftl, "ui_fr.ftl").unwrap();
+/// assert_eq!(value["popup"]["ok"], "D'accord");
+/// assert_eq!(value["popup"]["game_over"], "{n, plural, one {'#' perdu} other {Perdu}}");
+/// assert!(issues.is_empty());
+/// ```
+pub fn ftl_to_value(data: &str, source: &str) -> Result<(Value, Vec<KeyIssue>)> {
+    let resource = parser::parse(data).map_err(|(_, errors)| Error::I18n {
+        path: source.to_string(),
+        reason: errors
+            .iter()
+            .map(|e| format!("line {}: {e}", line_of(data, e.pos.start)))
+            .collect::<Vec<_>>()
+            .join("; "),
+    })?;
+
+    let mut converter = Converter::default();
+    for entry in &resource.body {
+        match entry {
+            Entry::Message(message) => {
+                let attributes = message
+                    .attributes
+                    .iter()
+                    .map(|a| (a.id.name, &a.value))
+                    .collect();
+                converter
+                    .messages
+                    .insert(message.id.name, (message.value.as_ref(), attributes));
+            }
+            Entry::Term(term) => {
+                let attributes = term
+                    .attributes
+                    .iter()
+                    .map(|a| (a.id.name, &a.value))
+                    .collect();
+                converter
+                    .terms
+                    .insert(term.id.name, (Some(&term.value), attributes));
+            }
+            _ => {}
+        }
+    }
+
+    let mut root = Map::new();
+    let mut issues = Vec::new();
+    let mut convert = |key: String, pattern: &Pattern<&str>| match converter.pattern(pattern, 0) {
+        Ok(text) => Some(Value::String(text)),
+        Err(reason) => {
+            issues.push(KeyIssue::Syntax {
+                source: source.to_string(),
+                key,
+                reason,
+            });
+            None
+        }
+    };
+    for entry in &resource.body {
+        let Entry::Message(message) = entry else {
+            continue;
+        };
+        let id = message.id.name;
+        if message.attributes.is_empty() {
+            if let Some(text) = message
+                .value
+                .as_ref()
+                .and_then(|p| convert(id.to_string(), p))
+            {
+                root.insert(id.to_string(), text);
+            }
+            continue;
+        }
+        // a group of labels, a value next to the attributes is not a label
+        let mut group = Map::new();
+        for attribute in &message.attributes {
+            let name = attribute.id.name;
+            if let Some(text) = convert(format!("{id}.{name}"), &attribute.value) {
+                group.insert(name.to_string(), text);
+            }
+        }
+        root.insert(id.to_string(), Value::Object(group));
+    }
+    Ok((Value::Object(root), issues))
+}
+
+// line number of the byte offset
+fn line_of(data: &str, offset: usize) -> usize {
+    data.as_bytes()[..offset.min(data.len())]
+        .iter()
+        .filter(|b| **b == b'\n')
+        .count()
+        + 1
+}
+
+// value and attributes of a message or a term
+type Entity<'r> = (
+    Option<&'r Pattern<&'r str>>,
+    HashMap<&'r str, &'r Pattern<&'r str>>,
+);
+
+// converts patterns, with the messages and terms they may reference
+#[derive(Default)]
+struct Converter<'r> {
+    messages: HashMap<&'r str, Entity<'r>>,
+    terms: HashMap<&'r str, Entity<'r>>,
+}
+
+impl Converter<'_> {
+    fn pattern(
+        &self,
+        pattern: &Pattern<&str>,
+        depth: usize,
+    ) -> std::result::Result<String, String> {
+        let mut out = String::new();
+        for element in &pattern.elements {
+            match element {
+                PatternElement::TextElement { value } => out.push_str(&quote(value)),
+                PatternElement::Placeable { expression } => {
+                    out.push_str(&self.expression(expression, depth)?)
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn expression(
+        &self,
+        expression: &Expression<&str>,
+        depth: usize,
+    ) -> std::result::Result<String, String> {
+        match expression {
+            Expression::Inline(inline) => self.inline(inline, depth),
+            Expression::Select { selector, variants } => self.select(selector, variants, depth),
+        }
+    }
+
+    fn inline(
+        &self,
+        inline: &InlineExpression<&str>,
+        depth: usize,
+    ) -> std::result::Result<String, String> {
+        match inline {
+            InlineExpression::StringLiteral { value } => {
+                Ok(quote(&unescape_unicode_to_string(value)))
+            }
+            InlineExpression::NumberLiteral { value } => Ok(quote(value)),
+            InlineExpression::VariableReference { id } => Ok(format!("{{{}}}", id.name)),
+            InlineExpression::FunctionReference { id, arguments } => {
+                number_function(id.name, arguments)
+            }
+            InlineExpression::MessageReference { id, attribute } => {
+                let entity = self.messages.get(id.name);
+                self.reference(entity, id.name, attribute.as_ref().map(|a| a.name), depth)
+            }
+            InlineExpression::TermReference { id, attribute, .. } => {
+                let entity = self.terms.get(id.name);
+                let name = format!("-{}", id.name);
+                self.reference(entity, &name, attribute.as_ref().map(|a| a.name), depth)
+            }
+            InlineExpression::Placeable { expression } => self.expression(expression, depth),
+        }
+    }
+
+    // inline the referenced message or term
+    fn reference(
+        &self,
+        entity: Option<&Entity<'_>>,
+        name: &str,
+        attribute: Option<&str>,
+        depth: usize,
+    ) -> std::result::Result<String, String> {
+        let (value, attributes) = entity.ok_or(format!("references the unknown `{name}`"))?;
+        let pattern = match attribute {
+            Some(attribute) => attributes.get(attribute).copied(),
+            None => *value,
+        }
+        .ok_or(format!("references `{name}` without that value"))?;
+        if depth >= MAX_DEPTH {
+            return Err(format!("references nest too deep at `{name}`"));
+        }
+        self.pattern(pattern, depth + 1)
+    }
+
+    fn select(
+        &self,
+        selector: &InlineExpression<&str>,
+        variants: &[Variant<&str>],
+        depth: usize,
+    ) -> std::result::Result<String, String> {
+        let (name, number) = match selector {
+            InlineExpression::VariableReference { id } => (id.name, false),
+            InlineExpression::FunctionReference { id, arguments } if id.name == "NUMBER" => {
+                match arguments.positional.first() {
+                    Some(InlineExpression::VariableReference { id }) => (id.name, true),
+                    _ => return Err("selects on NUMBER() of no variable".to_string()),
+                }
+            }
+            _ => return Err("selects on something else than a variable".to_string()),
+        };
+        let plural = number
+            || variants.iter().all(|v| match &v.key {
+                VariantKey::NumberLiteral { .. } => true,
+                VariantKey::Identifier { name } => PLURAL_CATEGORIES.contains(name),
+            });
+
+        let mut branches = Vec::new();
+        let mut default = None;
+        for variant in variants {
+            let text = self.pattern(&variant.value, depth)?;
+            let key = match &variant.key {
+                VariantKey::NumberLiteral { value } if plural => format!("={value}"),
+                VariantKey::NumberLiteral { value } => value.to_string(),
+                VariantKey::Identifier { name } => name.to_string(),
+            };
+            if variant.default {
+                default = Some(text.clone());
+            }
+            branches.push((key, text));
+        }
+        // the default variant is the `other` branch when there is none
+        if !branches.iter().any(|(key, _)| key == "other")
+            && let Some(text) = default
+        {
+            branches.push(("other".to_string(), text));
+        }
+
+        let kind = if plural { "plural" } else { "select" };
+        let branches: Vec<String> = branches
+            .iter()
+            .map(|(key, text)| format!("{key} {{{text}}}"))
+            .collect();
+        Ok(format!("{{{name}, {kind}, {}}}", branches.join(" ")))
+    }
+}
+
+// `NUMBER($name)`, with the `percent` style or no fraction digits
+fn number_function(
+    name: &str,
+    arguments: &CallArguments<&str>,
+) -> std::result::Result<String, String> {
+    if name != "NUMBER" {
+        return Err(format!("uses the unsupported function {name}()"));
+    }
+    let Some(InlineExpression::VariableReference { id }) = arguments.positional.first() else {
+        return Err("uses NUMBER() of no variable".to_string());
+    };
+    let style = arguments
+        .named
+        .iter()
+        .find_map(|arg| match (arg.name.name, &arg.value) {
+            ("style", InlineExpression::StringLiteral { value }) if *value == "percent" => {
+                Some(", percent")
+            }
+            ("maximumFractionDigits", InlineExpression::NumberLiteral { value })
+                if *value == "0" =>
+            {
+                Some(", integer")
+            }
+            _ => None,
+        })
+        .unwrap_or_default();
+    Ok(format!("{{{}, number{style}}}", id.name))
+}
@@ -0,0 +1,151 @@
+//! # font_check
+//!
+//! Check that the fonts of each locale can render its labels
+//!
+//! Every label of a locale is looked up, character by character, in the font
+//! chain of that locale. The names of all the languages are shown in the
+//! language combo, and the game symbols on the pop-ups, so they are checked
+//! against every chain. A character no face has a glyph for is drawn as a
+//! box, a gap of the report.
+use serde_json::Value;
+use std::fmt;
+
+use crate::customfonts::FontLibrary;
+use crate::error::Result;
+use crate::i18n::{DEFAULT_LOCALE, discover_locales, key_pointer, leaf_keys};
+use crate::pseudo::{PSEUDO_LOCALE, pseudo_language, pseudo_value};
+
+/// Gap: characters of a label no font of the locale can render
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gap {
+    pub locale: String,
+    /// Key of the label, or name of the symbol
+    pub key: String,
+    pub chars: Vec<char>,
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chars: Vec<String> = self
+            .chars
+            .iter()
+            .map(|c| format!("{c} U+{:04X}", *c as u32))
+            .collect();
+        write!(
+            f,
+            "{}: `{}` has no glyph for {}",
+            self.locale,
+            self.key,
+            chars.join(", ")
+        )
+    }
+}
+
+/// CoverageReport: the gaps of the fonts, and the faces that could not be loaded
+#[derive(Clone, Debug, Default)]
+pub struct CoverageReport {
+    pub unavailable: Vec<(String, String)>,
+    pub gaps: Vec<Gap>,
+}
+
+impl CoverageReport {
+    /// Whether every label can be rendered
+    pub fn is_clean(&self) -> bool {
+        self.gaps.is_empty()
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, reason) in &self.unavailable {
+            writeln!(f, "font {id}: {reason}")?;
+        }
+        if self.is_clean() {
+            return write!(f, "fonts cover every label");
+        }
+        for gap in &self.gaps {
+            writeln!(f, "{gap}")?;
+        }
+        write!(f, "{} gap(s)", self.gaps.len())
+    }
+}
+
+/// Check the labels of the locales, and the symbols, against the font chains
+///
+/// # Arguments
+///
+/// * `fonts`: &FontLibrary - the loaded faces
+/// * `locales`: &[(String, Value)] - the labels, by locale code
+/// * `symbols`: &[(&str, &str)] - name and text of the symbols shown in every locale
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+/// use util::customfonts::{FontLibrary, FontManifest};
+/// use util::font_check::check_coverage;
+///
+/// let json = r#"{ "faces": [{ "id": "Ubuntu-Light" }], "locales": { "en": ["Ubuntu-Light"] } }"#;
+/// let fonts = FontLibrary::from_manifest(FontManifest::from_json(json, "fonts.json").unwrap());
+/// let locales = [("en".to_string(), json!({ "popup": { "ok": "OK", "quit": "Quit ⏏" } }))];
+/// let report = check_coverage(&fonts, &locales, &[]);
+/// assert_eq!(report.gaps.len(), 1);
+/// assert_eq!(report.gaps[0].key, "popup.quit");
+/// ```
+pub fn check_coverage(
+    fonts: &FontLibrary,
+    locales: &[(String, Value)],
+    symbols: &[(&str, &str)],
+) -> CoverageReport {
+    let mut report = CoverageReport {
+        unavailable: fonts.unavailable().to_vec(),
+        gaps: Vec::new(),
+    };
+    let names: Vec<(String, String)> = locales
+        .iter()
+        .filter_map(|(code, value)| {
+            let name = value.pointer("/locale/name")?.as_str()?;
+            Some((format!("language {code}"), name.to_string()))
+        })
+        .collect();
+    for (locale, value) in locales {
+        let labels = leaf_keys(value).into_iter().filter_map(|key| {
+            let text = value.pointer(&key_pointer(&key))?.as_str()?.to_string();
+            Some((key, text))
+        });
+        let shared = names
+            .iter()
+            .cloned()
+            .chain(symbols.iter().map(|(k, t)| (k.to_string(), t.to_string())));
+        for (key, text) in labels.chain(shared) {
+            let chars = fonts.missing_glyphs(locale, &text);
+            if !chars.is_empty() {
+                report.gaps.push(Gap {
+                    locale: locale.clone(),
+                    key,
+                    chars,
+                });
+            }
+        }
+    }
+    report
+}
+
+/// Check the installed locales, and the pseudo-locale, against the font manifest
+///
+/// # Errors
+/// - Returns `Error::Asset` if the font manifest is missing or not valid
+/// - Returns `Error::I18n` if a locale file cannot be read or parsed
+pub fn check_installed(symbols: &[(&str, &str)]) -> Result<CoverageReport> {
+    let fonts = FontLibrary::load()?;
+    let mut locales = Vec::new();
+    for info in discover_locales() {
+        let (value, _) = info.load()?;
+        if info.language.code == DEFAULT_LOCALE {
+            let mut pseudo = pseudo_value(&value);
+            pseudo["locale"] = serde_json::to_value(pseudo_language()).unwrap_or_default();
+            locales.push((PSEUDO_LOCALE.to_string(), pseudo));
+        }
+        locales.push((info.language.code, value));
+    }
+    Ok(check_coverage(&fonts, &locales, symbols))
+}
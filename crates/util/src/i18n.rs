@@ -0,0 +1,534 @@
+//! # i18n
+//!
+//! Locale files of the game
+//!
+//! The locale files are embedded in the binary. Translators can point the
+//! game at a directory of their own files, with the `--i18n-dir` flag or the
+//! `FLYRUST_I18N_DIR` environment variable; a file found there replaces the
+//! embedded one of the same name.
+//!
+//! Every locale file declares its own code and display name:
+//!
+//! ```json
+//! { "locale": { "code": "zh-TW", "name": "繁體中文" }, ... }
+//! ```
+//!
+//! so adding a locale is dropping a file in either directory. Locale files are
+//! JSON, or Project Fluent `.ftl` files giving the same labels, see `fluent`.
+//! Within one directory a Fluent file replaces a JSON file of the same locale.
+//!
+//! A locale may name the locale its missing keys fall back to, the default
+//! locale is always the last one, then the built-in English labels:
+//!
+//! ```json
+//! { "locale": { "code": "zh-TW", "name": "繁體中文", "fallback": "zh-CN" }, ... }
+//! ```
+use include_dir::{Dir, include_dir};
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::assets::LanguageItem;
+use crate::error::{Error, Result};
+use crate::fluent::ftl_to_value;
+use crate::message::{Message, unknown_placeholders};
+
+/// Code of the locale every other one falls back to
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Environment variable of the translator override directory
+pub const I18N_DIR_ENV: &str = "FLYRUST_I18N_DIR";
+
+/// Locale files embedded at compile time
+pub static I18N: Dir = include_dir!("$CARGO_MANIFEST_DIR/../../Contents/Resources/assets/i18n");
+
+/// Override directory given on the command line
+static I18N_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Override the locale files with a directory, from the command line
+///
+/// Call it before the labels are loaded, only the first call counts.
+pub fn set_i18n_dir(dir: PathBuf) {
+    let _ = I18N_DIR_OVERRIDE.set(dir);
+}
+
+/// The translator override directory, if any
+pub fn i18n_dir() -> Option<PathBuf> {
+    I18N_DIR_OVERRIDE.get().cloned().or_else(|| {
+        env::var_os(I18N_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// I18nSource: where a locale file came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum I18nSource {
+    /// The translator override directory
+    Override(PathBuf),
+    /// The copy embedded in the binary
+    Embedded,
+    /// Made from the labels of another locale, like the pseudo-locale
+    Generated,
+}
+
+impl fmt::Display for I18nSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            I18nSource::Override(path) => write!(f, "{}", path.display()),
+            I18nSource::Embedded => write!(f, "embedded"),
+            I18nSource::Generated => write!(f, "generated"),
+        }
+    }
+}
+
+/// Read a locale file, from the override directory or the embedded copy
+///
+/// # Arguments
+///
+/// * `file_name`:&str - the locale file name, like `ui.json`
+///
+/// # Errors
+/// - Returns `Error::I18n` if the file is in neither place or cannot be read
+///
+/// # Examples
+/// ```
+/// use util::i18n::{I18nSource, read_locale_file};
+/// let (data, source) = read_locale_file("ui.json").unwrap();
+/// assert!(data.contains("\"app_name\""));
+/// # let _ = source;
+/// ```
+pub fn read_locale_file(file_name: &str) -> Result<(String, I18nSource)> {
+    let i18n_error = |reason: String| Error::I18n {
+        path: file_name.to_string(),
+        reason,
+    };
+
+    if let Some(dir) = i18n_dir() {
+        let path = dir.join(file_name);
+        if path.is_file() {
+            let data = fs::read_to_string(&path).map_err(|e| i18n_error(e.to_string()))?;
+            return Ok((data, I18nSource::Override(path)));
+        }
+    }
+
+    let data = I18N
+        .get_file(file_name)
+        .ok_or_else(|| i18n_error("not found".to_string()))?
+        .contents_utf8()
+        .ok_or_else(|| i18n_error("not UTF-8".to_string()))?;
+    Ok((data.to_string(), I18nSource::Embedded))
+}
+
+/// LocaleInfo: a locale found in the i18n directories
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocaleInfo {
+    /// Code and display name the file declares
+    pub language: LanguageItem,
+    /// File name of the locale, like `ui_zh-TW.json`
+    pub file_name: String,
+    /// Where the file is
+    pub source: I18nSource,
+}
+
+// the part of a locale file naming the locale
+#[derive(Deserialize)]
+struct LocaleHeader {
+    locale: LanguageItem,
+}
+
+/// Find the locales in the embedded and the override i18n directories
+///
+/// A file of the override directory replaces an embedded file declaring the
+/// same code. Files without a `locale` header are skipped. The default
+/// locale comes first, then the others by code.
+///
+/// # Examples
+/// ```
+/// use util::i18n::{DEFAULT_LOCALE, discover_locales};
+/// let locales = discover_locales();
+/// assert_eq!(locales[0].language.code, DEFAULT_LOCALE);
+/// assert!(locales.iter().any(|l| l.language.code == "zh-TW"));
+/// ```
+pub fn discover_locales() -> Vec<LocaleInfo> {
+    let mut files: Vec<(String, String, I18nSource)> = I18N
+        .files()
+        .filter_map(|file| {
+            let name = file.path().file_name()?.to_str()?.to_string();
+            let data = file.contents_utf8()?.to_string();
+            Some((name, data, I18nSource::Embedded))
+        })
+        .collect();
+    if let Some(dir) = i18n_dir()
+        && let Ok(entries) = fs::read_dir(&dir)
+    {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if let Some(name) = path.file_name().and_then(|n| n.to_str())
+                && let Ok(data) = fs::read_to_string(&path)
+            {
+                files.push((name.to_string(), data, I18nSource::Override(path.clone())));
+            }
+        }
+    }
+
+    // the override files, then the Fluent files of a directory, come last
+    files.retain(|(file_name, _, _)| is_locale_file(file_name));
+    files.sort_by_key(|(file_name, _, source)| {
+        (
+            matches!(source, I18nSource::Override(_)),
+            file_name.ends_with(".ftl"),
+            file_name.clone(),
+        )
+    });
+
+    let mut locales: Vec<LocaleInfo> = Vec::new();
+    for (file_name, data, source) in files {
+        let Ok((value, _)) = parse_locale(&file_name, &data, &source.to_string()) else {
+            continue;
+        };
+        let Ok(header) = serde_json::from_value::<LocaleHeader>(value) else {
+            continue;
+        };
+        let info = LocaleInfo {
+            language: header.locale,
+            file_name,
+            source,
+        };
+        match locales
+            .iter_mut()
+            .find(|l| l.language.code == info.language.code)
+        {
+            Some(found) => *found = info,
+            None => locales.push(info),
+        }
+    }
+
+    locales.sort_by(|a, b| {
+        let default = |l: &LocaleInfo| l.language.code != DEFAULT_LOCALE;
+        default(a)
+            .cmp(&default(b))
+            .then_with(|| a.language.code.cmp(&b.language.code))
+    });
+    locales
+}
+
+/// Whether the file name is of a locale file, JSON or Fluent
+pub fn is_locale_file(file_name: &str) -> bool {
+    file_name.ends_with(".json") || file_name.ends_with(".ftl")
+}
+
+/// Parse a locale file, JSON or Fluent by its file name
+///
+/// # Returns
+///
+/// * The labels, and the Fluent messages that could not be converted
+///
+/// # Errors
+/// - Returns `Error::I18n` if the file cannot be parsed, `source` names it
+pub fn parse_locale(file_name: &str, data: &str, source: &str) -> Result<(Value, Vec<KeyIssue>)> {
+    if file_name.ends_with(".ftl") {
+        return ftl_to_value(data, source);
+    }
+    let value = serde_json::from_str(data).map_err(|e| Error::I18n {
+        path: source.to_string(),
+        reason: e.to_string(),
+    })?;
+    Ok((value, Vec::new()))
+}
+
+impl LocaleInfo {
+    /// Read and parse the locale file
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if the file cannot be read or parsed
+    pub fn load(&self) -> Result<(Value, Vec<KeyIssue>)> {
+        parse_locale(&self.file_name, &self.read()?, &self.source.to_string())
+    }
+
+    /// Read the locale file
+    ///
+    /// # Errors
+    /// - Returns `Error::I18n` if the file cannot be read
+    pub fn read(&self) -> Result<String> {
+        match &self.source {
+            I18nSource::Override(path) => fs::read_to_string(path).map_err(|e| Error::I18n {
+                path: path.display().to_string(),
+                reason: e.to_string(),
+            }),
+            I18nSource::Embedded => I18N
+                .get_file(&self.file_name)
+                .and_then(|file| file.contents_utf8())
+                .map(str::to_string)
+                .ok_or_else(|| Error::I18n {
+                    path: self.file_name.clone(),
+                    reason: "not found".to_string(),
+                }),
+            I18nSource::Generated => Err(Error::I18n {
+                path: self.file_name.clone(),
+                reason: "generated, it has no file".to_string(),
+            }),
+        }
+    }
+}
+
+/// Find the discovered locale of the code
+pub fn find_locale(code: &str) -> Option<LocaleInfo> {
+    discover_locales()
+        .into_iter()
+        .find(|l| l.language.code == code)
+}
+
+/// The locales the keys of a locale resolve through, the locale first
+///
+/// The chain follows the `fallback` of each locale and ends with the default
+/// locale. It is empty when no locale has the code.
+///
+/// # Examples
+/// ```
+/// use util::i18n::fallback_chain;
+/// let codes: Vec<String> = fallback_chain("zh-TW")
+///     .into_iter()
+///     .map(|l| l.language.code)
+///     .collect();
+/// assert_eq!(codes, ["zh-TW", "zh-CN", "en"]);
+/// ```
+pub fn fallback_chain(code: &str) -> Vec<LocaleInfo> {
+    let locales = discover_locales();
+    let find = |code: &str| locales.iter().find(|l| l.language.code == code);
+
+    let mut chain: Vec<LocaleInfo> = Vec::new();
+    let mut next = Some(code.to_string());
+    while let Some(code) = next.take() {
+        if chain.iter().any(|l| l.language.code == code) {
+            break; // a fallback loop
+        }
+        match find(&code) {
+            Some(info) => {
+                next = info.language.fallback.clone();
+                chain.push(info.clone());
+            }
+            None if chain.is_empty() => return chain,
+            None => log::warn!("translation {code}: fallback locale not found"),
+        }
+    }
+    if !chain.iter().any(|l| l.language.code == DEFAULT_LOCALE)
+        && let Some(info) = find(DEFAULT_LOCALE)
+    {
+        chain.push(info.clone());
+    }
+    chain
+}
+
+/// KeyIssue: a key of a locale file that did not resolve as it is
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyIssue {
+    /// `source` has no text for the key, it came from `from`
+    Missing {
+        source: String,
+        key: String,
+        from: String,
+    },
+    /// The key of `source` is not a label of the game, or not text
+    Unknown { source: String, key: String },
+    /// The text of `source` uses an argument the source message does not have
+    Placeholder {
+        source: String,
+        key: String,
+        name: String,
+    },
+    /// The text of `source` is not a valid message
+    Syntax {
+        source: String,
+        key: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for KeyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyIssue::Missing { source, key, from } => {
+                write!(f, "translation {source}: missing key `{key}`, using {from}")
+            }
+            KeyIssue::Unknown { source, key } => {
+                write!(f, "translation {source}: unknown key `{key}`")
+            }
+            KeyIssue::Placeholder { source, key, name } => write!(
+                f,
+                "translation {source}: `{key}` uses `{{{name}}}` the source does not have"
+            ),
+            KeyIssue::Syntax {
+                source,
+                key,
+                reason,
+            } => write!(f, "translation {source}: `{key}` {reason}"),
+        }
+    }
+}
+
+/// Name of the built-in labels in the key issues
+pub const BUILT_IN: &str = "built-in";
+
+/// Resolve every key of the template through the named layers
+///
+/// The first layer with valid text for a key wins, the template holds the
+/// built-in text of every key. Text is valid when it parses as a message
+/// and uses only the arguments of the template text. The `locale` header is
+/// the one of the first layer. Keys the first layer misses, invalid text and
+/// keys of any layer the template does not have are reported.
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+/// use util::i18n::{KeyIssue, resolve_keys};
+///
+/// let template = json!({ "popup": { "ok": "OK", "game_over": "Game Over" } });
+/// let layers = [("fr".to_string(), json!({ "popup": { "ok": "D'accord" } }))];
+/// let (value, issues) = resolve_keys(&template, &layers);
+/// assert_eq!(value["popup"]["ok"], "D'accord");
+/// assert_eq!(value["popup"]["game_over"], "Game Over");
+/// assert!(matches!(&issues[0], KeyIssue::Missing { key, .. } if key == "popup.game_over"));
+/// ```
+pub fn resolve_keys(template: &Value, layers: &[(String, Value)]) -> (Value, Vec<KeyIssue>) {
+    let mut value = template.clone();
+    let mut issues = Vec::new();
+    let Some((source, first)) = layers.first() else {
+        return (value, issues);
+    };
+
+    for key in text_keys(template) {
+        let pointer = key_pointer(&key);
+        let source_message = template
+            .pointer(&pointer)
+            .and_then(Value::as_str)
+            .and_then(|text| Message::parse(text).ok());
+        let mut found = None;
+        for (name, layer) in layers {
+            let Some(text) = layer.pointer(&pointer).and_then(Value::as_str) else {
+                continue;
+            };
+            match (Message::parse(text), &source_message) {
+                (Err(e), _) => issues.push(KeyIssue::Syntax {
+                    source: name.clone(),
+                    key: key.clone(),
+                    reason: match e {
+                        Error::Message { reason, .. } => reason,
+                        e => e.to_string(),
+                    },
+                }),
+                (Ok(message), Some(source_message)) => {
+                    let unknown = unknown_placeholders(source_message, &message);
+                    if unknown.is_empty() {
+                        found = Some((name, text));
+                        break;
+                    }
+                    issues.extend(
+                        unknown
+                            .into_iter()
+                            .map(|placeholder| KeyIssue::Placeholder {
+                                source: name.clone(),
+                                key: key.clone(),
+                                name: placeholder,
+                            }),
+                    );
+                }
+                (Ok(_), None) => {
+                    found = Some((name, text));
+                    break;
+                }
+            }
+        }
+        match found {
+            Some((name, text)) => {
+                if name != source {
+                    issues.push(KeyIssue::Missing {
+                        source: source.clone(),
+                        key: key.clone(),
+                        from: name.clone(),
+                    });
+                }
+                if let Some(slot) = value.pointer_mut(&pointer) {
+                    *slot = Value::String(text.to_string());
+                }
+            }
+            None => issues.push(KeyIssue::Missing {
+                source: source.clone(),
+                key: key.clone(),
+                from: BUILT_IN.to_string(),
+            }),
+        }
+    }
+
+    for (name, layer) in layers {
+        for key in leaf_keys(layer) {
+            if template
+                .pointer(&key_pointer(&key))
+                .is_none_or(|t| !t.is_string())
+                || layer
+                    .pointer(&key_pointer(&key))
+                    .is_some_and(|v| !v.is_string())
+            {
+                issues.push(KeyIssue::Unknown {
+                    source: name.clone(),
+                    key,
+                });
+            }
+        }
+    }
+
+    if let Some(locale) = first.get("locale")
+        && let Some(slot) = value.get_mut("locale")
+    {
+        *slot = locale.clone();
+    }
+    (value, issues)
+}
+
+// dotted keys of the text leaves of the labels, the locale header aside
+fn text_keys(value: &Value) -> Vec<String> {
+    leaf_keys(value)
+        .into_iter()
+        .filter(|key| {
+            value
+                .pointer(&key_pointer(key))
+                .is_some_and(Value::is_string)
+        })
+        .collect()
+}
+
+// dotted keys of every leaf of the labels, the locale header aside
+pub(crate) fn leaf_keys(value: &Value) -> Vec<String> {
+    fn walk(value: &Value, prefix: &str, keys: &mut Vec<String>) {
+        match value.as_object() {
+            Some(map) => {
+                for (name, child) in map {
+                    if prefix.is_empty() && name == "locale" {
+                        continue;
+                    }
+                    let key = if prefix.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{prefix}.{name}")
+                    };
+                    walk(child, &key, keys);
+                }
+            }
+            None => keys.push(prefix.to_string()),
+        }
+    }
+    let mut keys = Vec::new();
+    if value.is_object() {
+        walk(value, "", &mut keys);
+    }
+    keys
+}
+
+// JSON pointer of a dotted key
+pub(crate) fn key_pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
@@ -0,0 +1,324 @@
+//! # i18n_check
+//!
+//! Check the translations against the English source
+//!
+//! Every locale file of a directory is compared with the file of the default
+//! locale, key by key: missing keys, extra keys, text left in English and
+//! placeholders that differ from the source are reported. The locale files
+//! are kept twice, in `Contents/Resources/assets/i18n` and in the
+//! `dist/FlyRust.app` bundle, and the two copies are compared file by file.
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::i18n::{DEFAULT_LOCALE, is_locale_file, key_pointer, leaf_keys, parse_locale};
+use crate::message::Message;
+
+/// The locale files of the source tree, relative to the workspace root
+pub const I18N_SOURCE_DIR: &str = "Contents/Resources/assets/i18n";
+
+/// The copy of the locale files in the app bundle, relative to the workspace root
+pub const I18N_BUNDLE_DIR: &str = "dist/FlyRust.app/Contents/Resources/assets/i18n";
+
+/// Finding: a problem of a translation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Finding {
+    /// The locale has no text for a key of the source
+    Missing { locale: String, key: String },
+    /// The locale has a key the source does not have
+    Extra { locale: String, key: String },
+    /// The text of the locale is the English text
+    Untranslated { locale: String, key: String },
+    /// The placeholders of the text differ from the ones of the source
+    Placeholder {
+        locale: String,
+        key: String,
+        missing: Vec<String>,
+        extra: Vec<String>,
+    },
+    /// The text is not a valid message
+    Syntax {
+        locale: String,
+        key: String,
+        reason: String,
+    },
+    /// A locale file cannot be read or parsed
+    File { path: PathBuf, reason: String },
+    /// The copies of a locale file differ, or one is missing
+    Drift {
+        file_name: String,
+        source: PathBuf,
+        copy: PathBuf,
+    },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Missing { locale, key } => write!(f, "{locale}: missing `{key}`"),
+            Finding::Extra { locale, key } => write!(f, "{locale}: extra `{key}`"),
+            Finding::Untranslated { locale, key } => write!(f, "{locale}: untranslated `{key}`"),
+            Finding::Placeholder {
+                locale,
+                key,
+                missing,
+                extra,
+            } => {
+                write!(
+                    f,
+                    "{locale}: placeholders of `{key}` differ from the source"
+                )?;
+                if !missing.is_empty() {
+                    write!(f, ", missing {{{}}}", missing.join("}, {"))?;
+                }
+                if !extra.is_empty() {
+                    write!(f, ", extra {{{}}}", extra.join("}, {"))?;
+                }
+                Ok(())
+            }
+            Finding::Syntax {
+                locale,
+                key,
+                reason,
+            } => write!(f, "{locale}: `{key}` {reason}"),
+            Finding::File { path, reason } => write!(f, "{}: {reason}", path.display()),
+            Finding::Drift {
+                file_name,
+                source,
+                copy,
+            } => write!(
+                f,
+                "{file_name}: {} and {} differ",
+                source.display(),
+                copy.display()
+            ),
+        }
+    }
+}
+
+/// Report: the findings of a check
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    /// Whether nothing was found
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "translations are complete");
+        }
+        for finding in &self.findings {
+            writeln!(f, "{finding}")?;
+        }
+        write!(f, "{} finding(s)", self.findings.len())
+    }
+}
+
+/// Check the locale files of a directory against its default locale file
+///
+/// # Errors
+/// - Returns `Error::I18n` if the directory cannot be read or has no default locale file
+pub fn check_dir(dir: &Path) -> Result<Report> {
+    let mut report = Report::default();
+    let mut locales: Vec<(String, Value)> = Vec::new();
+    for path in locale_files(dir)? {
+        let file_name = file_name(&path);
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                parse_locale(&file_name, &data, &path.display().to_string())
+                    .map_err(|e| e.to_string())
+            });
+        match parsed {
+            Ok((value, issues)) => {
+                let code = value
+                    .pointer("/locale/code")
+                    .and_then(Value::as_str)
+                    .map_or_else(|| file_name.clone(), str::to_string);
+                report
+                    .findings
+                    .extend(issues.into_iter().map(|issue| Finding::File {
+                        path: path.clone(),
+                        reason: issue.to_string(),
+                    }));
+                locales.push((code, value));
+            }
+            Err(reason) => report.findings.push(Finding::File { path, reason }),
+        }
+    }
+
+    let source = locales
+        .iter()
+        .find(|(code, _)| code == DEFAULT_LOCALE)
+        .map(|(_, value)| value.clone())
+        .ok_or_else(|| Error::I18n {
+            path: dir.display().to_string(),
+            reason: format!("no {DEFAULT_LOCALE} locale file"),
+        })?;
+    for (locale, value) in &locales {
+        if locale != DEFAULT_LOCALE {
+            report.findings.extend(check_locale(locale, value, &source));
+        }
+    }
+    Ok(report)
+}
+
+/// Compare the labels of a locale with the ones of the source, key by key
+///
+/// # Examples
+/// ```
+/// use serde_json::json;
+/// use util::i18n_check::{Finding, check_locale};
+///
+/// let source = json!({ "popup": { "ok": "OK", "game_over": "Game Over" } });
+/// let fr = json!({ "popup": { "ok": "OK", "quit": "Quitter" } });
+/// let findings = check_locale("fr", &fr, &source);
+/// let key = |k: &str| k.to_string();
+/// assert!(findings.contains(&Finding::Missing { locale: key("fr"), key: key("popup.game_over") }));
+/// assert!(findings.contains(&Finding::Extra { locale: key("fr"), key: key("popup.quit") }));
+/// assert!(findings.contains(&Finding::Untranslated { locale: key("fr"), key: key("popup.ok") }));
+/// ```
+pub fn check_locale(locale: &str, value: &Value, source: &Value) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let text = |value: &Value, key: &str| {
+        value
+            .pointer(&key_pointer(key))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    for key in leaf_keys(source) {
+        let Some(source_text) = text(source, &key) else {
+            continue;
+        };
+        let Some(translated) = text(value, &key) else {
+            findings.push(Finding::Missing {
+                locale: locale.to_string(),
+                key,
+            });
+            continue;
+        };
+        // text without letters, like the difficulty stars, needs no translation
+        if translated == source_text && source_text.chars().any(char::is_alphabetic) {
+            findings.push(Finding::Untranslated {
+                locale: locale.to_string(),
+                key: key.clone(),
+            });
+        }
+        match (Message::parse(&source_text), Message::parse(&translated)) {
+            (Ok(source_message), Ok(message)) => {
+                let expected = source_message.placeholders();
+                let found = message.placeholders();
+                let missing: Vec<String> = expected.difference(&found).cloned().collect();
+                let extra: Vec<String> = found.difference(&expected).cloned().collect();
+                if !missing.is_empty() || !extra.is_empty() {
+                    findings.push(Finding::Placeholder {
+                        locale: locale.to_string(),
+                        key,
+                        missing,
+                        extra,
+                    });
+                }
+            }
+            (_, Err(e)) => findings.push(Finding::Syntax {
+                locale: locale.to_string(),
+                key,
+                reason: match e {
+                    Error::Message { reason, .. } => reason,
+                    e => e.to_string(),
+                },
+            }),
+            (Err(_), Ok(_)) => {}
+        }
+    }
+
+    let source_keys: BTreeSet<String> = leaf_keys(source).into_iter().collect();
+    for key in leaf_keys(value) {
+        if !source_keys.contains(&key) {
+            findings.push(Finding::Extra {
+                locale: locale.to_string(),
+                key,
+            });
+        }
+    }
+    findings
+}
+
+/// Compare the locale files of two directories, file by file
+///
+/// # Errors
+/// - Returns `Error::I18n` if a directory cannot be read
+pub fn check_drift(source: &Path, copy: &Path) -> Result<Vec<Finding>> {
+    let names = |dir: &Path| -> Result<BTreeSet<String>> {
+        Ok(locale_files(dir)?.iter().map(|p| file_name(p)).collect())
+    };
+    let mut findings = Vec::new();
+    for name in names(source)?.union(&names(copy)?) {
+        let (a, b) = (source.join(name), copy.join(name));
+        if fs::read(&a).ok() != fs::read(&b).ok() {
+            findings.push(Finding::Drift {
+                file_name: name.clone(),
+                source: a,
+                copy: b,
+            });
+        }
+    }
+    Ok(findings)
+}
+
+/// Check the translations of the workspace and the app bundle copy
+///
+/// # Errors
+/// - Returns `Error::I18n` if the locale directories cannot be read
+pub fn check_workspace(root: &Path) -> Result<Report> {
+    let source = root.join(I18N_SOURCE_DIR);
+    let mut report = check_dir(&source)?;
+    let bundle = root.join(I18N_BUNDLE_DIR);
+    if bundle.is_dir() {
+        report.findings.extend(check_drift(&source, &bundle)?);
+    }
+    Ok(report)
+}
+
+/// Test helper: panic with the report unless the translations of the workspace are complete
+///
+/// # Examples
+/// ```no_run
+/// util::i18n_check::assert_translations_complete(std::path::Path::new("."));
+/// ```
+pub fn assert_translations_complete(root: &Path) {
+    match check_workspace(root) {
+        Ok(report) => assert!(report.is_clean(), "{report}"),
+        Err(e) => panic!("{e}"),
+    }
+}
+
+// locale files of the directory, by name
+fn locale_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(|e| Error::I18n {
+        path: dir.display().to_string(),
+        reason: e.to_string(),
+    })?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_locale_file(&file_name(path)))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
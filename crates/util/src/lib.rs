@@ -1,13 +1,30 @@
 #![doc = include_str!("../README.md")]
 
 // mod
+pub mod asset_manifest;
 pub mod assets;
 pub mod customfonts;
+pub mod data_dir;
+pub mod error;
+pub mod fluent;
+pub mod font_check;
+pub mod i18n;
+pub mod i18n_check;
+pub mod message;
+pub mod migration;
+pub mod pseudo;
+pub mod resource;
+pub mod score;
+pub mod settings;
+pub mod skin;
 pub mod sqlite;
 pub mod utilities;
 
 // use
 pub use customfonts::setup_custom_fonts;
+pub use error::{Error, Result};
+pub use score::{MemoryScore, ScoreRepository};
 pub use sqlite::MyScore;
+pub use utilities::get_data_path;
 pub use utilities::get_resource_path_str;
 pub use utilities::what_panic;
@@ -0,0 +1,633 @@
+//! # message
+//!
+//! Fill the translated messages with named arguments
+//!
+//! Messages use a subset of the ICU message syntax:
+//!
+//! * `{name}` - the argument, numbers in the number format of the locale
+//! * `{name, number}` - the number, also `{name, number, integer}` and
+//!   `{name, number, percent}`
+//! * `{name, plural, =0 {none} one {# fly} other {# flies}}` - the branch of
+//!   the exact value or of the CLDR plural category of the locale, `#` is the
+//!   number
+//! * `{name, select, hard {...} other {...}}` - the branch of the text
+//!
+//! Every plural and select needs an `other` branch. An apostrophe quotes the
+//! special characters, `'{'` is a brace, `'#'` a hash and `''` an apostrophe;
+//! other apostrophes are text.
+use intl_pluralrules::{PluralCategory, PluralRuleType, PluralRules};
+use std::collections::BTreeSet;
+use std::fmt;
+use unic_langid::LanguageIdentifier;
+
+use crate::error::{Error, Result};
+
+/// Arg: a typed argument of a message
+#[derive(Clone, Debug, PartialEq)]
+pub enum Arg {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Arg {
+    // the argument as a number, if it is one
+    fn number(&self) -> Option<f64> {
+        match self {
+            Arg::Int(n) => Some(*n as f64),
+            Arg::Float(x) => Some(*x),
+            Arg::Text(text) => text.trim().parse().ok(),
+        }
+    }
+}
+
+impl fmt::Display for Arg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::Int(n) => write!(f, "{n}"),
+            Arg::Float(x) => write!(f, "{x}"),
+            Arg::Text(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+macro_rules! int_arg {
+    ($($ty:ty)+) => {
+        $(impl From<$ty> for Arg {
+            fn from(n: $ty) -> Self {
+                Arg::Int(n as i64)
+            }
+        })+
+    };
+}
+int_arg!(i32 i64 u32 u64 usize);
+
+impl From<f32> for Arg {
+    fn from(x: f32) -> Self {
+        Arg::Float(x as f64)
+    }
+}
+
+impl From<f64> for Arg {
+    fn from(x: f64) -> Self {
+        Arg::Float(x)
+    }
+}
+
+impl From<&str> for Arg {
+    fn from(text: &str) -> Self {
+        Arg::Text(text.to_string())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(text: String) -> Self {
+        Arg::Text(text)
+    }
+}
+
+/// Args: the named arguments of a message
+///
+/// # Examples
+/// ```
+/// use util::message::{Args, format_message};
+/// let args = Args::new().with("count", 1200).with("path", "/tmp/scores.json");
+/// assert_eq!(
+///     format_message("en", "Exported {count} scores to {path}", &args),
+///     "Exported 1,200 scores to /tmp/scores.json"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Args {
+    args: Vec<(String, Arg)>,
+}
+
+impl Args {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the argument, replacing one of the same name
+    pub fn with(mut self, name: &str, arg: impl Into<Arg>) -> Self {
+        self.args.retain(|(n, _)| n != name);
+        self.args.push((name.to_string(), arg.into()));
+        self
+    }
+
+    /// The argument of the name
+    pub fn get(&self, name: &str) -> Option<&Arg> {
+        self.args
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, arg)| arg)
+    }
+}
+
+// style of a number placeholder
+#[derive(Clone, Debug, PartialEq)]
+enum NumberStyle {
+    Decimal,
+    Integer,
+    Percent,
+}
+
+// a part of a parsed message
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Text(String),
+    // `#` of a plural branch
+    Number,
+    Arg(String),
+    NumberArg(String, NumberStyle),
+    Plural(String, Vec<(String, Vec<Part>)>),
+    Select(String, Vec<(String, Vec<Part>)>),
+}
+
+/// Message: a parsed message template
+///
+/// # Examples
+/// ```
+/// use util::message::{Args, Message};
+/// let message = Message::parse("{n, plural, =0 {no fly} one {# fly} other {# flies}}").unwrap();
+/// assert_eq!(message.format("en", &Args::new().with("n", 0)), "no fly");
+/// assert_eq!(message.format("en", &Args::new().with("n", 1)), "1 fly");
+/// assert_eq!(message.format("en", &Args::new().with("n", 2)), "2 flies");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    parts: Vec<Part>,
+}
+
+impl Message {
+    /// Parse the message template
+    ///
+    /// # Errors
+    /// - Returns `Error::Message` if the template is not valid
+    pub fn parse(template: &str) -> Result<Self> {
+        let chars: Vec<char> = template.chars().collect();
+        let mut parser = Parser { chars, pos: 0 };
+        let parsed = parser.parts(false).and_then(|parts| match parser.peek() {
+            Some(_) => Err(format!("unmatched `}}` at {}", parser.pos)),
+            None => Ok(parts),
+        });
+        let parts = parsed.map_err(|reason| Error::Message {
+            message: template.to_string(),
+            reason,
+        })?;
+        Ok(Self { parts })
+    }
+
+    /// Names of the arguments the message uses
+    pub fn placeholders(&self) -> BTreeSet<String> {
+        fn collect(parts: &[Part], names: &mut BTreeSet<String>) {
+            for part in parts {
+                match part {
+                    Part::Text(_) | Part::Number => {}
+                    Part::Arg(name) | Part::NumberArg(name, _) => {
+                        names.insert(name.clone());
+                    }
+                    Part::Plural(name, branches) | Part::Select(name, branches) => {
+                        names.insert(name.clone());
+                        for (_, parts) in branches {
+                            collect(parts, names);
+                        }
+                    }
+                }
+            }
+        }
+        let mut names = BTreeSet::new();
+        collect(&self.parts, &mut names);
+        names
+    }
+
+    /// The template of the message with its text changed, the placeholders kept
+    ///
+    /// # Examples
+    /// ```
+    /// use util::message::Message;
+    /// let message = Message::parse("{n, plural, one {# fly} other {# flies}}").unwrap();
+    /// assert_eq!(
+    ///     message.map_text(|text| text.to_uppercase()),
+    ///     "{n, plural, one {# FLY} other {# FLIES}}"
+    /// );
+    /// ```
+    pub fn map_text(&self, f: impl Fn(&str) -> String) -> String {
+        let mut out = String::new();
+        write_template(&mut out, &self.parts, &f);
+        out
+    }
+
+    /// Fill the message with the arguments, in the formats of the locale
+    ///
+    /// A missing argument is left as `{name}`.
+    pub fn format(&self, locale: &str, args: &Args) -> String {
+        let mut out = String::new();
+        let plurals = plural_rules(locale);
+        write_parts(&mut out, &self.parts, locale, &plurals, args, None);
+        out
+    }
+}
+
+/// Fill the message template with the arguments, in the formats of the locale
+///
+/// A template that does not parse is returned as it is.
+pub fn format_message(locale: &str, template: &str, args: &Args) -> String {
+    match Message::parse(template) {
+        Ok(message) => message.format(locale, args),
+        Err(_) => template.to_string(),
+    }
+}
+
+/// Quote the text so it parses as itself
+///
+/// Only the apostrophes that would start a quote are doubled, so plain text
+/// stays as it is.
+///
+/// # Examples
+/// ```
+/// use util::message::{Args, Message, quote};
+/// assert_eq!(quote("D'accord"), "D'accord");
+/// let message = Message::parse(&quote("{score} isn't # yet'")).unwrap();
+/// assert_eq!(message.format("en", &Args::new()), "{score} isn't # yet'");
+/// ```
+pub fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if matches!(chars.peek(), None | Some('\'' | '{' | '}' | '#')) => {
+                out.push_str("''")
+            }
+            '{' | '}' | '#' => {
+                out.push('\'');
+                out.push(c);
+                out.push('\'');
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Placeholders of the translation the source message does not have
+///
+/// # Examples
+/// ```
+/// use util::message::{Message, unknown_placeholders};
+/// let source = Message::parse("You got {score} Rustaceans.").unwrap();
+/// let translation = Message::parse("你抓到了 {scor} 隻。").unwrap();
+/// assert_eq!(unknown_placeholders(&source, &translation), ["scor"]);
+/// ```
+pub fn unknown_placeholders(source: &Message, translation: &Message) -> Vec<String> {
+    let known = source.placeholders();
+    translation
+        .placeholders()
+        .into_iter()
+        .filter(|name| !known.contains(name))
+        .collect()
+}
+
+/// CLDR plural category name of the number in the locale
+///
+/// # Examples
+/// ```
+/// use util::message::plural_category;
+/// assert_eq!(plural_category("en", 1.0), "one");
+/// assert_eq!(plural_category("en", 1.5), "other");
+/// assert_eq!(plural_category("zh-TW", 1.0), "other");
+/// ```
+pub fn plural_category(locale: &str, n: f64) -> &'static str {
+    category_name(select(&plural_rules(locale), n))
+}
+
+// cardinal plural rules of the locale, or of its language, English when
+// neither has any
+fn plural_rules(locale: &str) -> PluralRules {
+    let create = |id: &str| {
+        let id: LanguageIdentifier = id.parse().ok()?;
+        PluralRules::create(id, PluralRuleType::CARDINAL).ok()
+    };
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    create(locale)
+        .or_else(|| create(language))
+        .or_else(|| create("en"))
+        .expect("English has plural rules")
+}
+
+fn select(rules: &PluralRules, n: f64) -> PluralCategory {
+    rules
+        .select(n.to_string().as_str())
+        .unwrap_or(PluralCategory::OTHER)
+}
+
+fn category_name(category: PluralCategory) -> &'static str {
+    match category {
+        PluralCategory::ZERO => "zero",
+        PluralCategory::ONE => "one",
+        PluralCategory::TWO => "two",
+        PluralCategory::FEW => "few",
+        PluralCategory::MANY => "many",
+        PluralCategory::OTHER => "other",
+    }
+}
+
+/// Format the number in the number format of the locale
+///
+/// Up to three fraction digits are kept.
+///
+/// # Examples
+/// ```
+/// use util::message::format_number;
+/// assert_eq!(format_number("en", 1234567.0), "1,234,567");
+/// assert_eq!(format_number("de", 1234.5), "1.234,5");
+/// ```
+pub fn format_number(locale: &str, n: f64) -> String {
+    let (decimal, group) = separators(locale);
+    let rounded = (n.abs() * 1000.0).round() / 1000.0;
+    let text = format!("{rounded}");
+    let (int, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+    let mut out = String::new();
+    if n < 0.0 && rounded != 0.0 {
+        out.push('-');
+    }
+    for (i, digit) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            out.push_str(group);
+        }
+        out.push(digit);
+    }
+    if !fraction.is_empty() {
+        out.push_str(decimal);
+        out.push_str(fraction);
+    }
+    out
+}
+
+// decimal and group separators of the locale
+fn separators(locale: &str) -> (&'static str, &'static str) {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    match language {
+        "de" | "es" | "it" | "pt" | "nl" | "id" | "tr" | "da" => (",", "."),
+        "fr" | "ru" | "pl" | "cs" | "sv" | "fi" | "nb" | "uk" => (",", "\u{a0}"),
+        _ => (".", ","),
+    }
+}
+
+fn write_template(out: &mut String, parts: &[Part], f: &impl Fn(&str) -> String) {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(&quote(&f(text))),
+            Part::Number => out.push('#'),
+            Part::Arg(name) => out.push_str(&format!("{{{name}}}")),
+            Part::NumberArg(name, style) => out.push_str(&match style {
+                NumberStyle::Decimal => format!("{{{name}, number}}"),
+                NumberStyle::Integer => format!("{{{name}, number, integer}}"),
+                NumberStyle::Percent => format!("{{{name}, number, percent}}"),
+            }),
+            Part::Plural(name, branches) | Part::Select(name, branches) => {
+                let kind = match part {
+                    Part::Plural(..) => "plural",
+                    _ => "select",
+                };
+                out.push_str(&format!("{{{name}, {kind},"));
+                for (key, parts) in branches {
+                    out.push_str(&format!(" {key} {{"));
+                    write_template(out, parts, f);
+                    out.push('}');
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_parts(
+    out: &mut String,
+    parts: &[Part],
+    locale: &str,
+    plurals: &PluralRules,
+    args: &Args,
+    number: Option<f64>,
+) {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Number => match number {
+                Some(n) => out.push_str(&format_number(locale, n)),
+                None => out.push('#'),
+            },
+            Part::Arg(name) => match args.get(name) {
+                Some(Arg::Text(text)) => out.push_str(text),
+                Some(arg) => out.push_str(&format_number(locale, arg.number().unwrap_or(0.0))),
+                None => out.push_str(&format!("{{{name}}}")),
+            },
+            Part::NumberArg(name, style) => match args.get(name) {
+                Some(arg) => match arg.number() {
+                    Some(n) => out.push_str(&match style {
+                        NumberStyle::Decimal => format_number(locale, n),
+                        NumberStyle::Integer => format_number(locale, n.round()),
+                        NumberStyle::Percent => format!("{}%", format_number(locale, n * 100.0)),
+                    }),
+                    None => out.push_str(&arg.to_string()),
+                },
+                None => out.push_str(&format!("{{{name}}}")),
+            },
+            Part::Plural(name, branches) => {
+                let Some(n) = args.get(name).and_then(Arg::number) else {
+                    out.push_str(&format!("{{{name}}}"));
+                    continue;
+                };
+                let category = category_name(select(plurals, n));
+                let exact = branches.iter().find(|(key, _)| {
+                    key.strip_prefix('=')
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .is_some_and(|v| v == n)
+                });
+                if let Some((_, parts)) = exact
+                    .or_else(|| branches.iter().find(|(key, _)| key == category))
+                    .or_else(|| branches.iter().find(|(key, _)| key == "other"))
+                {
+                    write_parts(out, parts, locale, plurals, args, Some(n));
+                }
+            }
+            Part::Select(name, branches) => {
+                let Some(value) = args.get(name).map(Arg::to_string) else {
+                    out.push_str(&format!("{{{name}}}"));
+                    continue;
+                };
+                if let Some((_, parts)) = branches
+                    .iter()
+                    .find(|(key, _)| *key == value)
+                    .or_else(|| branches.iter().find(|(key, _)| key == "other"))
+                {
+                    write_parts(out, parts, locale, plurals, args, number);
+                }
+            }
+        }
+    }
+}
+
+// recursive descent parser of the message syntax
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    // the text up to one of the stop characters, trimmed
+    fn word(&mut self, stops: &[char]) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !stops.contains(&c)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    fn expect(&mut self, c: char) -> std::result::Result<(), String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(found) => Err(format!("expected `{c}` at {}, found `{found}`", self.pos)),
+            None => Err(format!("expected `{c}` at the end")),
+        }
+    }
+
+    // parts up to the end, or up to the `}` closing a branch
+    fn parts(&mut self, in_plural: bool) -> std::result::Result<Vec<Part>, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    self.pos += 1;
+                    parts.push(self.placeholder()?);
+                }
+                '}' => break,
+                '\'' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\'') => {
+                            text.push('\'');
+                            self.pos += 1;
+                        }
+                        Some('{' | '}' | '#') => {
+                            // quoted up to the next apostrophe
+                            while let Some(c) = self.peek() {
+                                self.pos += 1;
+                                if c == '\'' {
+                                    break;
+                                }
+                                text.push(c);
+                            }
+                        }
+                        _ => text.push('\''),
+                    }
+                }
+                '#' if in_plural => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    self.pos += 1;
+                    parts.push(Part::Number);
+                }
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(parts)
+    }
+
+    // a placeholder, after its `{`
+    fn placeholder(&mut self) -> std::result::Result<Part, String> {
+        let name = self.word(&[',', '}']);
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("bad argument name `{name}` at {}", self.pos));
+        }
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Part::Arg(name));
+        }
+        self.expect(',')?;
+        let kind = self.word(&[',', '}']);
+        let part = match kind.as_str() {
+            "number" => {
+                let style = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    match self.word(&['}']).as_str() {
+                        "integer" => NumberStyle::Integer,
+                        "percent" => NumberStyle::Percent,
+                        style => return Err(format!("unknown number style `{style}`")),
+                    }
+                } else {
+                    NumberStyle::Decimal
+                };
+                Part::NumberArg(name, style)
+            }
+            "plural" | "select" => {
+                self.expect(',')?;
+                let branches = self.branches(kind == "plural")?;
+                if !branches.iter().any(|(key, _)| key == "other") {
+                    return Err(format!("{kind} of `{name}` has no `other` branch"));
+                }
+                if kind == "plural" {
+                    Part::Plural(name, branches)
+                } else {
+                    Part::Select(name, branches)
+                }
+            }
+            kind => return Err(format!("unknown placeholder type `{kind}` of `{name}`")),
+        };
+        self.expect('}')?;
+        Ok(part)
+    }
+
+    // `key {message}` branches, up to the `}` closing the placeholder
+    fn branches(
+        &mut self,
+        in_plural: bool,
+    ) -> std::result::Result<Vec<(String, Vec<Part>)>, String> {
+        let mut branches = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => return Ok(branches),
+                None => return Err("unclosed placeholder".to_string()),
+                _ => {}
+            }
+            let key = self.word(&['{', '}']);
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(format!("bad branch key `{key}` at {}", self.pos));
+            }
+            self.expect('{')?;
+            let parts = self.parts(in_plural)?;
+            self.expect('}')?;
+            branches.push((key, parts));
+        }
+    }
+}
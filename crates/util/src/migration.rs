@@ -0,0 +1,171 @@
+//! # migration
+//!
+//! Versioned schema migrations of the score database
+//!
+//! The schema version is kept in SQLite `user_version`. Opening a database
+//! runs every step newer than its version, in order, each step in its own
+//! transaction with the version bump, so a failed step leaves the database
+//! at the last good version.
+use rusqlite::{Connection, Result};
+
+use crate::score::{BOARD_SIZE, StorageError};
+
+/// Schema version written by this binary
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Migration: one upgrade step of the schema
+struct Migration {
+    /// Version of the schema after the step
+    version: u32,
+    /// Apply the step
+    up: fn(&Connection) -> Result<()>,
+}
+
+/// Upgrade steps in version order
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up: create_scores,
+    },
+    Migration {
+        version: 2,
+        up: add_boards,
+    },
+    Migration {
+        version: 3,
+        up: add_personal_bests,
+    },
+];
+
+/// Read the schema version of the database, 0 for a new or unversioned one
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Upgrade the database to `SCHEMA_VERSION`
+///
+/// # Returns
+///
+/// * The schema version the database was at before the upgrade
+///
+/// # Errors
+/// - Returns `StorageError::TooNew` without touching a database newer than the binary
+/// - Returns `StorageError::Sqlite` if a step fails, the database stays at the last good version
+///
+/// # Examples
+/// ```
+/// use rusqlite::Connection;
+/// use util::migration::{SCHEMA_VERSION, migrate, schema_version};
+///
+/// let mut conn = Connection::open_in_memory().unwrap();
+/// assert_eq!(migrate(&mut conn).unwrap(), 0);
+/// assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+/// ```
+pub fn migrate(conn: &mut Connection) -> std::result::Result<u32, StorageError> {
+    let found = schema_version(conn)?;
+    if found > SCHEMA_VERSION {
+        return Err(StorageError::TooNew {
+            found,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > found) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    Ok(found)
+}
+
+// v1: the score table
+//
+// Databases from before the versioning already have it.
+fn create_scores(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS flyplayer (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            score INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// v2: a board per difficulty and game mode
+//
+// Unversioned databases can have the board columns already. Old scores go
+// to the Easy Classic board.
+fn add_boards(conn: &Connection) -> Result<()> {
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(flyplayer)")?
+        .query_map([], |row| row.get("name"))?
+        .collect::<Result<_>>()?;
+    for (column, definition) in [
+        ("difficulty", "TEXT NOT NULL DEFAULT 'Easy'"),
+        ("mode", "TEXT NOT NULL DEFAULT 'Classic'"),
+        ("duration", "REAL NOT NULL DEFAULT 10.0"),
+        ("app_version", "TEXT NOT NULL DEFAULT ''"),
+    ] {
+        if !columns.iter().any(|c| c == column) {
+            conn.execute(
+                &format!("ALTER TABLE flyplayer ADD COLUMN {column} {definition}"),
+                [],
+            )?;
+        }
+    }
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS flyplayer_board
+        ON flyplayer (difficulty, mode, score DESC)",
+        [],
+    )?;
+    Ok(())
+}
+
+// v3: personal bests in their own table, one row per player and board
+//
+// The best score of each player is copied from the boards, then the boards
+// keep their best BOARD_SIZE scores only.
+fn add_personal_bests(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS personal_best (
+            name TEXT NOT NULL,
+            score INTEGER NOT NULL,
+            difficulty TEXT NOT NULL,
+            mode TEXT NOT NULL,
+            duration REAL NOT NULL,
+            app_version TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (name, difficulty, mode)
+        )",
+        [],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO personal_best
+            (name, score, difficulty, mode, duration, app_version, created_at)
+        SELECT name, score, difficulty, mode, duration, app_version, created_at FROM (
+            SELECT *, ROW_NUMBER() OVER (
+                PARTITION BY name, difficulty, mode ORDER BY score DESC, id ASC
+            ) AS player_rank
+            FROM flyplayer
+        )
+        WHERE player_rank = 1",
+        [],
+    )?;
+    conn.execute(
+        "DELETE FROM flyplayer WHERE id IN (
+            SELECT id FROM (
+                SELECT id, ROW_NUMBER() OVER (
+                    PARTITION BY difficulty, mode ORDER BY score DESC, id ASC
+                ) AS board_rank
+                FROM flyplayer
+            )
+            WHERE board_rank > ?1
+        )",
+        [BOARD_SIZE as i64],
+    )?;
+    Ok(())
+}
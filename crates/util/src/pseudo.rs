@@ -0,0 +1,125 @@
+//! # pseudo
+//!
+//! Pseudo-localization, to see the labels that do not fit before a
+//! translator finds them
+//!
+//! The pseudo-locale `en-XA` is made from the English labels: every letter
+//! is accented, every label is bracketed and padded by 40%, like a long
+//! translation, and the placeholders are kept so the messages still format:
+//!
+//! `You got {score} Rustaceans.` → `[Ýöû ĝöţ {score} Ŕûšţáçéáñš. ~~~~~~~~~~~]`
+use serde_json::Value;
+
+use crate::assets::LanguageItem;
+use crate::i18n::DEFAULT_LOCALE;
+use crate::message::{Message, quote};
+
+/// Code of the pseudo-locale
+pub const PSEUDO_LOCALE: &str = "en-XA";
+
+// padding, in percent of the length of the label
+const PADDING_PERCENT: usize = 40;
+
+/// The language item of the pseudo-locale
+pub fn pseudo_language() -> LanguageItem {
+    LanguageItem {
+        code: PSEUDO_LOCALE.to_string(),
+        name: "[Þšéûðö]".to_string(),
+        fallback: Some(DEFAULT_LOCALE.to_string()),
+    }
+}
+
+/// Pseudo-localize a message, its placeholders are kept
+///
+/// # Examples
+/// ```
+/// use util::message::{Args, format_message};
+/// use util::pseudo::pseudo_text;
+///
+/// let pseudo = pseudo_text("Score: {score}");
+/// assert_eq!(pseudo, "[Šçöŕé: {score} ~~~~~~]");
+/// assert_eq!(format_message("en", &pseudo, &Args::new().with("score", 3)), "[Šçöŕé: 3 ~~~~~~]");
+/// ```
+pub fn pseudo_text(text: &str) -> String {
+    let pad = (text.chars().count() * PADDING_PERCENT)
+        .div_ceil(100)
+        .max(1);
+    let template = match Message::parse(text) {
+        Ok(message) => message.map_text(accent),
+        Err(_) => quote(&accent(text)),
+    };
+    format!("[{template} {}]", "~".repeat(pad))
+}
+
+/// Pseudo-localize every label of a locale, the `locale` header aside
+pub fn pseudo_value(value: &Value) -> Value {
+    fn walk(value: &Value, top: bool) -> Value {
+        match value {
+            Value::String(text) => Value::String(pseudo_text(text)),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, child)| {
+                        let child = if top && key == "locale" {
+                            child.clone()
+                        } else {
+                            walk(child, false)
+                        };
+                        (key.clone(), child)
+                    })
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+    walk(value, true)
+}
+
+// accent the ASCII letters
+fn accent(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'a' => 'á',
+            'c' => 'ç',
+            'd' => 'ð',
+            'e' => 'é',
+            'f' => 'ƒ',
+            'g' => 'ĝ',
+            'h' => 'ĥ',
+            'i' => 'î',
+            'j' => 'ĵ',
+            'k' => 'ķ',
+            'l' => 'ļ',
+            'n' => 'ñ',
+            'o' => 'ö',
+            'p' => 'þ',
+            'r' => 'ŕ',
+            's' => 'š',
+            't' => 'ţ',
+            'u' => 'û',
+            'w' => 'ŵ',
+            'y' => 'ý',
+            'z' => 'ž',
+            'A' => 'Å',
+            'C' => 'Ç',
+            'D' => 'Ð',
+            'E' => 'É',
+            'G' => 'Ĝ',
+            'H' => 'Ĥ',
+            'I' => 'Î',
+            'J' => 'Ĵ',
+            'K' => 'Ķ',
+            'L' => 'Ļ',
+            'N' => 'Ñ',
+            'O' => 'Ö',
+            'P' => 'Þ',
+            'R' => 'Ŕ',
+            'S' => 'Š',
+            'T' => 'Ţ',
+            'U' => 'Û',
+            'W' => 'Ŵ',
+            'Y' => 'Ý',
+            'Z' => 'Ž',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,246 @@
+//! # resource
+//!
+//! Find the resource files of the game
+//!
+//! Resources are looked up by their path relative to the resource root,
+//! like `assets/image/hand.png`, in an ordered list of roots:
+//! 1. the `FLYRUST_RESOURCE_DIR` environment variable
+//! 2. the directory of the executable
+//! 3. the system share directories, `$XDG_DATA_DIRS/flyrust` on Unix
+//! 4. the macOS bundle `Contents/Resources`, also the workspace layout of
+//!    `cargo run`
+//! 5. the workspace `Contents/Resources` of the source, in debug builds, for
+//!    tests and other target directories
+//! 6. the copy embedded in the binary
+//!
+//! Every lookup reports the root that had the file.
+use include_dir::Dir;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assets::ASSETS;
+use crate::error::{Error, Result};
+
+/// Environment variable adding a resource root before the others
+pub const RESOURCE_DIR_ENV: &str = "FLYRUST_RESOURCE_DIR";
+
+/// Kind of a resource root
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootKind {
+    /// `FLYRUST_RESOURCE_DIR`
+    Env,
+    /// Directory of the executable
+    ExeDir,
+    /// System share directory
+    SystemShare,
+    /// macOS bundle, or the workspace when run by cargo
+    Bundle,
+    /// Workspace of the source, in debug builds
+    Workspace,
+    /// Embedded in the binary
+    Embedded,
+}
+
+/// ResourceRoot: a place resources are looked up in
+#[derive(Clone, Debug)]
+pub enum ResourceRoot {
+    /// A directory on disk
+    Dir { kind: RootKind, dir: PathBuf },
+    /// A directory embedded in the binary, mounted at `prefix`
+    Embedded {
+        prefix: &'static str,
+        dir: &'static Dir<'static>,
+    },
+}
+
+impl ResourceRoot {
+    pub fn kind(&self) -> RootKind {
+        match self {
+            ResourceRoot::Dir { kind, .. } => *kind,
+            ResourceRoot::Embedded { .. } => RootKind::Embedded,
+        }
+    }
+}
+
+impl fmt::Display for ResourceRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceRoot::Dir { kind, dir } => write!(f, "{:?} {}", kind, dir.display()),
+            ResourceRoot::Embedded { prefix, .. } => write!(f, "Embedded {prefix}"),
+        }
+    }
+}
+
+/// Resource: a resource file found in a root
+#[derive(Clone, Debug)]
+pub enum Resource {
+    /// A file on disk
+    File(PathBuf),
+    /// A file embedded in the binary
+    Embedded(&'static [u8]),
+}
+
+/// Located: where a lookup found the resource
+#[derive(Clone, Debug)]
+pub struct Located {
+    /// The root that had the resource
+    pub root: ResourceRoot,
+    pub resource: Resource,
+}
+
+impl Located {
+    /// Read the resource
+    ///
+    /// # Errors
+    /// - Returns `Error::Path` if the file cannot be read
+    pub fn read(&self) -> Result<Vec<u8>> {
+        match &self.resource {
+            Resource::File(path) => fs::read(path).map_err(|e| Error::Path {
+                path: path.clone(),
+                reason: e.to_string(),
+            }),
+            Resource::Embedded(data) => Ok(data.to_vec()),
+        }
+    }
+
+    /// Read the resource as UTF-8 text
+    ///
+    /// # Errors
+    /// - Returns `Error::Path` if the file cannot be read or is not UTF-8
+    pub fn read_to_string(&self) -> Result<String> {
+        String::from_utf8(self.read()?).map_err(|_| Error::Path {
+            path: self.path().unwrap_or_default(),
+            reason: "not UTF-8".to_string(),
+        })
+    }
+
+    /// Path of the resource on disk, None when embedded
+    pub fn path(&self) -> Option<PathBuf> {
+        match &self.resource {
+            Resource::File(path) => Some(path.clone()),
+            Resource::Embedded(_) => None,
+        }
+    }
+}
+
+/// ResourceLocator: look up resources in the ordered roots
+///
+/// # Examples
+/// ```
+/// use util::resource::{ResourceLocator, RootKind};
+///
+/// let locator = ResourceLocator::new();
+/// let located = locator.locate("assets/image/hand.png").unwrap();
+/// println!("hand.png from {}", located.root);
+/// assert!(locator.locate("assets/nothing.png").is_none());
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResourceLocator {
+    roots: Vec<ResourceRoot>,
+}
+
+impl Default for ResourceLocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ResourceLocator {
+    /// The standard roots of the running game
+    pub fn new() -> Self {
+        let exe = env::current_exe().ok();
+        let mut roots = Vec::new();
+        let mut dir = |kind, dir: PathBuf| roots.push(ResourceRoot::Dir { kind, dir });
+
+        if let Some(env_dir) = env::var_os(RESOURCE_DIR_ENV).filter(|d| !d.is_empty()) {
+            dir(RootKind::Env, PathBuf::from(env_dir));
+        }
+        if let Some(exe_dir) = exe.as_deref().and_then(Path::parent) {
+            dir(RootKind::ExeDir, exe_dir.to_path_buf());
+        }
+        for share in system_share_dirs() {
+            dir(RootKind::SystemShare, share.join("flyrust"));
+        }
+        // FlyRust.app/Contents/MacOS/flyrust, or <workspace>/target/debug/flyrust
+        if let Some(app_root) = exe.as_deref().and_then(|exe| exe.ancestors().nth(3)) {
+            dir(
+                RootKind::Bundle,
+                app_root.join("Contents").join("Resources"),
+            );
+        }
+        if cfg!(debug_assertions) {
+            let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+            dir(
+                RootKind::Workspace,
+                workspace.join("Contents").join("Resources"),
+            );
+        }
+        roots.push(ResourceRoot::Embedded {
+            prefix: "assets",
+            dir: &ASSETS,
+        });
+        Self { roots }
+    }
+
+    /// Look up the resources in these roots only
+    pub fn with_roots(roots: Vec<ResourceRoot>) -> Self {
+        Self { roots }
+    }
+
+    /// The roots, in lookup order
+    pub fn roots(&self) -> &[ResourceRoot] {
+        &self.roots
+    }
+
+    /// Find the resource in the first root that has it
+    pub fn locate(&self, relative_path: &str) -> Option<Located> {
+        self.roots.iter().find_map(|root| {
+            let resource = match root {
+                ResourceRoot::Dir { dir, .. } => {
+                    let path = dir.join(relative_path);
+                    path.is_file().then_some(Resource::File(path))?
+                }
+                ResourceRoot::Embedded { prefix, dir } => {
+                    let inner = Path::new(relative_path).strip_prefix(prefix).ok()?;
+                    Resource::Embedded(dir.get_file(inner)?.contents())
+                }
+            };
+            Some(Located {
+                root: root.clone(),
+                resource,
+            })
+        })
+    }
+
+    /// Find the resource, or say where it was looked for
+    ///
+    /// # Errors
+    /// - Returns `Error::Path` if no root has the resource
+    pub fn require(&self, relative_path: &str) -> Result<Located> {
+        self.locate(relative_path).ok_or_else(|| Error::Path {
+            path: PathBuf::from(relative_path),
+            reason: format!(
+                "not found in {}",
+                self.roots
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        })
+    }
+}
+
+// system share directories, in lookup order
+fn system_share_dirs() -> Vec<PathBuf> {
+    if !cfg!(unix) {
+        return Vec::new();
+    }
+    let dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    env::split_paths(&dirs).collect()
+}
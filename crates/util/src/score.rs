@@ -0,0 +1,347 @@
+//! # score
+//!
+//! Leaderboards behind a storage-independent repository
+//!
+//! `ScoreRepository` is implemented by the SQLite store `MyScore` and by
+//! `MemoryScore`, which keeps the scores of the session in memory.
+//! `ScoreStore` puts the two together, so the game keeps a leaderboard
+//! when the database cannot be used.
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::sqlite::MyScore;
+
+/// Players shown on a board
+pub const BOARD_SIZE: usize = 10;
+
+/// Board: the leaderboard of a difficulty and game mode
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Board {
+    pub difficulty: String,
+    pub mode: String,
+}
+
+impl Board {
+    pub fn new(difficulty: &str, mode: &str) -> Self {
+        Self {
+            difficulty: difficulty.to_string(),
+            mode: mode.to_string(),
+        }
+    }
+}
+
+/// ScoreEntry: one score on a board
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    /// Player name
+    pub name: String,
+    /// Rustaceans hunted
+    pub score: i32,
+    /// Board of the round
+    pub board: Board,
+    /// Round duration in seconds
+    pub duration: f32,
+    /// Version of the game that played the round
+    pub app_version: String,
+    /// Creation timestamp, `%Y-%m-%d %H:%M:%S`
+    pub created_at: String,
+}
+
+impl ScoreEntry {
+    /// A score of a round played now by the version of the game
+    pub fn new(name: &str, score: i32, board: Board, duration: f32, app_version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            score,
+            board,
+            duration,
+            app_version: app_version.to_string(),
+            created_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+/// StorageError: the score storage cannot be opened or used
+#[derive(Debug)]
+pub enum StorageError {
+    /// The database was written by a newer version of the game
+    TooNew { found: u32, supported: u32 },
+    /// SQLite failed
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::TooNew { found, supported } => write!(
+                f,
+                "score database version {found} is newer than the supported version {supported}"
+            ),
+            StorageError::Sqlite(e) => write!(f, "score database error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Sqlite(e) => Some(e),
+            StorageError::TooNew { .. } => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+/// ScoreRepository: where the leaderboards are kept
+///
+/// Boards rank by score, best first, and the earlier score first on a tie.
+pub trait ScoreRepository {
+    /// The best `limit` scores of the board
+    fn top(&self, board: &Board, limit: usize) -> Result<Vec<ScoreEntry>>;
+
+    /// Add a score
+    fn insert(&mut self, entry: &ScoreEntry) -> Result<()>;
+
+    /// The best score of the player on the board
+    fn personal_best(&self, name: &str, board: &Board) -> Result<Option<ScoreEntry>>;
+}
+
+/// MemoryScore: leaderboards kept in memory, lost on exit
+///
+/// # Examples
+/// ```
+/// use util::score::{Board, MemoryScore, ScoreEntry, ScoreRepository};
+///
+/// let mut scores = MemoryScore::default();
+/// let board = Board::new("Easy", "Classic");
+/// scores.insert(&ScoreEntry::new("Ferris", 12, board.clone(), 10.0, "0.1.0")).unwrap();
+/// scores.insert(&ScoreEntry::new("Crab", 15, board.clone(), 10.0, "0.1.0")).unwrap();
+///
+/// let top = scores.top(&board, 10).unwrap();
+/// assert_eq!(top[0].name, "Crab");
+/// assert_eq!(scores.personal_best("Ferris", &board).unwrap().unwrap().score, 12);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryScore {
+    entries: Vec<ScoreEntry>, // in insertion order
+}
+
+impl MemoryScore {
+    /// All scores, in insertion order
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    /// Remove and return all scores, in insertion order
+    pub fn take(&mut self) -> Vec<ScoreEntry> {
+        std::mem::take(&mut self.entries)
+    }
+
+    // scores of the board, ranked
+    fn ranked<'a>(&'a self, board: &'a Board) -> impl Iterator<Item = &'a ScoreEntry> {
+        let mut ranked: Vec<&ScoreEntry> =
+            self.entries.iter().filter(|e| &e.board == board).collect();
+        // stable sort keeps the earlier score first on a tie
+        ranked.sort_by_key(|e| std::cmp::Reverse(e.score));
+        ranked.into_iter()
+    }
+}
+
+impl ScoreRepository for MemoryScore {
+    fn top(&self, board: &Board, limit: usize) -> Result<Vec<ScoreEntry>> {
+        Ok(self.ranked(board).take(limit).cloned().collect())
+    }
+
+    fn insert(&mut self, entry: &ScoreEntry) -> Result<()> {
+        self.entries.push(entry.clone());
+        Ok(())
+    }
+
+    fn personal_best(&self, name: &str, board: &Board) -> Result<Option<ScoreEntry>> {
+        Ok(self.ranked(board).find(|e| e.name == name).cloned())
+    }
+}
+
+/// Opens the storage of a ScoreStore
+pub type OpenStorage = Box<dyn FnMut() -> Result<Box<dyn ScoreRepository>>>;
+
+/// ScoreStore: the score storage, or a session leaderboard without it
+///
+/// When the storage cannot be opened or written, the scores of the session
+/// are kept in memory. `retry` opens the storage again and moves them into
+/// it, and `export` saves them to a file meanwhile.
+///
+/// # Examples
+/// ```
+/// use util::Error;
+/// use util::score::{Board, ScoreEntry, ScoreRepository, ScoreStore};
+///
+/// let mut store = ScoreStore::new(Box::new(|| {
+///     Err(Error::Path {
+///         path: "flyrust.db".into(),
+///         reason: "read-only".to_string(),
+///     })
+/// }));
+/// assert!(store.is_session_only());
+///
+/// let board = Board::new("Easy", "Classic");
+/// store.insert(&ScoreEntry::new("Ferris", 12, board.clone(), 10.0, "0.1.0")).unwrap();
+/// assert_eq!(store.top(&board, 10).unwrap()[0].name, "Ferris");
+/// ```
+pub struct ScoreStore {
+    open: OpenStorage,
+    storage: Option<Box<dyn ScoreRepository>>,
+    session: MemoryScore,  // scores not in the storage
+    error: Option<String>, // why the storage is not used
+}
+
+impl ScoreStore {
+    /// Open the storage, or start with the session leaderboard
+    pub fn new(open: OpenStorage) -> Self {
+        let mut store = Self {
+            open,
+            storage: None,
+            session: MemoryScore::default(),
+            error: None,
+        };
+        let _ = store.retry();
+        store
+    }
+
+    /// Keep the scores in the SQLite database file
+    pub fn sqlite(path: PathBuf) -> Self {
+        Self::new(Box::new(move || {
+            MyScore::open(&path).map(|scores| Box::new(scores) as Box<dyn ScoreRepository>)
+        }))
+    }
+
+    /// The scores are kept for this session only
+    pub fn is_session_only(&self) -> bool {
+        self.storage.is_none()
+    }
+
+    /// Why the storage is not used
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Scores of the session not in the storage
+    pub fn pending(&self) -> &[ScoreEntry] {
+        self.session.entries()
+    }
+
+    /// Open the storage again and move the session scores into it
+    ///
+    /// # Errors
+    /// - Returns the error of the storage, the scores left stay in the session
+    pub fn retry(&mut self) -> Result<()> {
+        let mut storage = match self.storage.take() {
+            Some(storage) => storage,
+            None => match (self.open)() {
+                Ok(storage) => storage,
+                Err(e) => return Err(self.fail(e)),
+            },
+        };
+
+        let mut pending = self.session.take().into_iter();
+        while let Some(entry) = pending.next() {
+            if let Err(e) = storage.insert(&entry) {
+                for entry in std::iter::once(entry).chain(pending) {
+                    let _ = self.session.insert(&entry);
+                }
+                return Err(self.fail(e));
+            }
+        }
+
+        self.storage = Some(storage);
+        self.error = None;
+        Ok(())
+    }
+
+    /// Save the scores of the session as a JSON file
+    ///
+    /// # Returns
+    ///
+    /// * The number of scores saved
+    ///
+    /// # Errors
+    /// - Returns `Error::Path` if the file cannot be written
+    pub fn export(&self, path: &Path) -> Result<usize> {
+        let path_error = |reason: String| Error::Path {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let data = serde_json::to_string_pretty(self.session.entries())
+            .map_err(|e| path_error(e.to_string()))?;
+        fs::write(path, data).map_err(|e| path_error(e.to_string()))?;
+        Ok(self.session.entries().len())
+    }
+
+    /// Save the scores of the session in the first directory that can be written
+    ///
+    /// # Returns
+    ///
+    /// * The file written and the number of scores saved
+    ///
+    /// # Errors
+    /// - Returns `Error::Path` of the last directory if none can be written
+    pub fn export_to(&self, dirs: &[PathBuf], name: &str) -> Result<(PathBuf, usize)> {
+        let mut last = Error::Path {
+            path: PathBuf::from(name),
+            reason: "no directory to export to".to_string(),
+        };
+        for dir in dirs {
+            let path = dir.join(name);
+            match self.export(&path) {
+                Ok(count) => return Ok((path, count)),
+                Err(e) => last = e,
+            }
+        }
+        Err(last)
+    }
+
+    // stop using the storage
+    fn fail(&mut self, e: Error) -> Error {
+        self.storage = None;
+        self.error = Some(e.to_string());
+        e
+    }
+}
+
+impl ScoreRepository for ScoreStore {
+    fn top(&self, board: &Board, limit: usize) -> Result<Vec<ScoreEntry>> {
+        match &self.storage {
+            Some(storage) => storage.top(board, limit),
+            None => self.session.top(board, limit),
+        }
+    }
+
+    /// Add a score, to the session when the storage fails
+    fn insert(&mut self, entry: &ScoreEntry) -> Result<()> {
+        if let Some(storage) = self.storage.as_mut() {
+            match storage.insert(entry) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    self.fail(e);
+                }
+            }
+        }
+        self.session.insert(entry)
+    }
+
+    fn personal_best(&self, name: &str, board: &Board) -> Result<Option<ScoreEntry>> {
+        match &self.storage {
+            Some(storage) => storage.personal_best(name, board),
+            None => self.session.personal_best(name, board),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! # settings
+//!
+//! The choices of the player kept between sessions, `settings.json` in the
+//! data directory
+//!
+//! A missing or unreadable file gives the default settings, the game still
+//! starts.
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::utilities::get_data_path;
+
+/// The settings file, in the data directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Settings: the choices of the player
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// Id of the skin pack, the default look without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skin: Option<String>,
+}
+
+impl Settings {
+    /// The settings file of the data directory
+    pub fn default_path() -> PathBuf {
+        get_data_path(SETTINGS_FILE)
+    }
+
+    /// Read the settings, the defaults when the file is missing or not valid
+    ///
+    /// # Examples
+    /// ```
+    /// use util::settings::Settings;
+    /// let settings = Settings::load(std::path::Path::new("no/such/settings.json"));
+    /// assert_eq!(settings, Settings::default());
+    /// ```
+    pub fn load(path: &Path) -> Self {
+        let Ok(data) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&data).unwrap_or_else(|e| {
+            log::warn!("settings {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    /// Write the settings
+    ///
+    /// # Errors
+    /// - Returns `Error::Path` if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let path_error = |reason: String| Error::Path {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let data = serde_json::to_string_pretty(self).map_err(|e| path_error(e.to_string()))?;
+        fs::write(path, data).map_err(|e| path_error(e.to_string()))
+    }
+}
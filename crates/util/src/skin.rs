@@ -0,0 +1,313 @@
+//! # skin
+//!
+//! Skin packs: textures, sizes, colours and fonts over the default look
+//!
+//! A skin pack is a directory, or a `.zip` archive of one, in the `skins`
+//! directory of the data directory. Its `skin.json` names the pack and
+//! overrides any part of the look, the files are paths in the pack:
+//!
+//! ```json
+//! {
+//!     "name": "Halloween",
+//!     "textures": { "Rustacean": "bat.png", "Beach": "graveyard.png" },
+//!     "sizes": { "fly": 72, "hand": 40 },
+//!     "colors": { "text": "#ff9900", "background": "#8080c0" },
+//!     "fonts": ["creepy.ttf"]
+//! }
+//! ```
+//!
+//! The textures are the ids of the asset manifest. The sizes are in points,
+//! a fly up to the margin of the game world. The colours tint the images,
+//! white keeps them as they are, and `text` is the colour of the labels.
+//! The fonts come before the fonts of the locale.
+use eframe::egui::Color32;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::error::{Error, Result};
+use crate::utilities::get_data_path;
+
+/// The skins directory, in the data directory
+pub const SKINS_DIR: &str = "skins";
+
+/// The description of a skin pack, at the root of the pack
+pub const SKIN_FILE: &str = "skin.json";
+
+/// Size of the flies, in points
+pub const DEFAULT_FLY_SIZE: f32 = 60.0;
+
+/// Size of the hand cursor, in points
+pub const DEFAULT_HAND_SIZE: f32 = 50.0;
+
+/// Largest fly of a skin, the margin of the game world: the flies stay that
+/// far from the window border
+pub const MAX_FLY_SIZE: f32 = 80.0;
+
+/// Largest hand cursor of a skin
+pub const MAX_HAND_SIZE: f32 = 100.0;
+
+/// Look: the sizes and colours of the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct Look {
+    pub fly_size: f32,
+    pub hand_size: f32,
+    /// Colour of the labels, the one of the theme by default
+    pub text: Option<Color32>,
+    /// Tint of the flies
+    pub fly: Color32,
+    /// Tint of the hand cursor
+    pub hand: Color32,
+    /// Tint of the background
+    pub background: Color32,
+}
+
+impl Default for Look {
+    fn default() -> Self {
+        Self {
+            fly_size: DEFAULT_FLY_SIZE,
+            hand_size: DEFAULT_HAND_SIZE,
+            text: None,
+            fly: Color32::WHITE,
+            hand: Color32::WHITE,
+            background: Color32::WHITE,
+        }
+    }
+}
+
+// skin.json
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkinFile {
+    name: String,
+    #[serde(default)]
+    textures: BTreeMap<String, String>,
+    #[serde(default)]
+    sizes: SkinSizes,
+    #[serde(default)]
+    colors: SkinColors,
+    #[serde(default)]
+    fonts: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkinSizes {
+    fly: Option<f32>,
+    hand: Option<f32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkinColors {
+    text: Option<String>,
+    fly: Option<String>,
+    hand: Option<String>,
+    background: Option<String>,
+}
+
+// where the files of the pack are
+#[derive(Clone, Debug)]
+enum SkinFiles {
+    Dir(PathBuf),
+    Archive(HashMap<String, Vec<u8>>),
+}
+
+/// Skin: a skin pack
+#[derive(Clone, Debug)]
+pub struct Skin {
+    /// Name of the directory or archive, kept in the settings
+    pub id: String,
+    /// Name shown in the skin selector
+    pub name: String,
+    pub path: PathBuf,
+    pub look: Look,
+    /// File of the pack, by texture id
+    pub textures: BTreeMap<String, String>,
+    /// Font files of the pack
+    pub fonts: Vec<String>,
+    files: SkinFiles,
+}
+
+impl Skin {
+    /// Load a skin pack, a directory or a `.zip` archive
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the pack cannot be read, its `skin.json` is
+    ///   not valid, or it names files it does not have
+    pub fn load(path: &Path) -> Result<Self> {
+        let asset_error = |reason: String| Error::Asset {
+            path: path.display().to_string(),
+            reason,
+        };
+        let files = if path.is_dir() {
+            SkinFiles::Dir(path.to_path_buf())
+        } else {
+            let data = fs::read(path).map_err(|e| asset_error(e.to_string()))?;
+            SkinFiles::Archive(read_zip(&data).map_err(asset_error)?)
+        };
+        let id = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut skin = Self {
+            id,
+            name: String::new(),
+            path: path.to_path_buf(),
+            look: Look::default(),
+            textures: BTreeMap::new(),
+            fonts: Vec::new(),
+            files,
+        };
+
+        let data = skin.read(SKIN_FILE)?;
+        let file: SkinFile =
+            serde_json::from_slice(&data).map_err(|e| asset_error(format!("{SKIN_FILE}: {e}")))?;
+        let size = |name: &str, size: Option<f32>, default: f32, max: f32| match size {
+            Some(size) if !(size > 0.0 && size <= max) => Err(asset_error(format!(
+                "{SKIN_FILE}: {name} size {size} is not in 0..{max}"
+            ))),
+            Some(size) => Ok(size),
+            None => Ok(default),
+        };
+        let color = |name: &str, hex: &Option<String>| -> Result<Option<Color32>> {
+            hex.as_deref()
+                .map(|hex| {
+                    Color32::from_hex(hex)
+                        .map_err(|_| asset_error(format!("{SKIN_FILE}: {name} colour {hex}")))
+                })
+                .transpose()
+        };
+        let colors = &file.colors;
+        skin.look = Look {
+            fly_size: size("fly", file.sizes.fly, DEFAULT_FLY_SIZE, MAX_FLY_SIZE)?,
+            hand_size: size("hand", file.sizes.hand, DEFAULT_HAND_SIZE, MAX_HAND_SIZE)?,
+            text: color("text", &colors.text)?,
+            fly: color("fly", &colors.fly)?.unwrap_or(Color32::WHITE),
+            hand: color("hand", &colors.hand)?.unwrap_or(Color32::WHITE),
+            background: color("background", &colors.background)?.unwrap_or(Color32::WHITE),
+        };
+        skin.name = file.name;
+        skin.textures = file.textures;
+        skin.fonts = file.fonts;
+        for name in skin.textures.values().chain(&skin.fonts) {
+            if !skin.has(name) {
+                return Err(asset_error(format!(
+                    "{SKIN_FILE}: {name} is not in the pack"
+                )));
+            }
+        }
+        Ok(skin)
+    }
+
+    /// Read a file of the pack
+    ///
+    /// # Errors
+    /// - Returns `Error::Asset` if the pack has no such file
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let asset_error = |reason: String| Error::Asset {
+            path: format!("{}: {name}", self.path.display()),
+            reason,
+        };
+        match &self.files {
+            SkinFiles::Dir(dir) => {
+                let path = pack_path(name)
+                    .map(|p| dir.join(p))
+                    .ok_or_else(|| asset_error("not a path in the pack".to_string()))?;
+                fs::read(path).map_err(|e| asset_error(e.to_string()))
+            }
+            SkinFiles::Archive(files) => files
+                .get(name)
+                .cloned()
+                .ok_or_else(|| asset_error("not found".to_string())),
+        }
+    }
+
+    // whether the pack has the file
+    fn has(&self, name: &str) -> bool {
+        match &self.files {
+            SkinFiles::Dir(dir) => pack_path(name).is_some_and(|p| dir.join(p).is_file()),
+            SkinFiles::Archive(files) => files.contains_key(name),
+        }
+    }
+}
+
+/// The skins directory of the data directory
+pub fn skins_dir() -> PathBuf {
+    get_data_path(SKINS_DIR)
+}
+
+/// The skin packs of a directory, by name, a pack that cannot be loaded is logged and left out
+pub fn discover_skins(dir: &Path) -> Vec<Skin> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.join(SKIN_FILE).is_file()
+                || path
+                    .extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+        })
+        .collect();
+    paths.sort();
+    let mut skins: Vec<Skin> = paths
+        .iter()
+        .filter_map(|path| {
+            Skin::load(path)
+                .inspect_err(|e| log::warn!("skin {e}"))
+                .ok()
+        })
+        .collect();
+    skins.sort_by(|a, b| a.name.cmp(&b.name));
+    skins
+}
+
+// a relative path that stays in the pack
+fn pack_path(name: &str) -> Option<&Path> {
+    let path = Path::new(name);
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then_some(path)
+}
+
+// the files of a zip archive, by path
+//
+// an archive of the pack directory has its files under the directory, the
+// paths are taken from there
+fn read_zip(data: &[u8]) -> std::result::Result<HashMap<String, Vec<u8>>, String> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| e.to_string())?;
+    let mut files = HashMap::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)
+            .map_err(|e| format!("{name}: {e}"))?;
+        files.insert(name, content);
+    }
+
+    // the files of `<dir>/skin.json` archives
+    if !files.contains_key(SKIN_FILE)
+        && let Some(prefix) = files
+            .keys()
+            .find_map(|name| name.strip_suffix(SKIN_FILE))
+            .filter(|prefix| prefix.matches('/').count() == 1)
+            .map(str::to_string)
+    {
+        files = files
+            .into_iter()
+            .filter_map(|(name, content)| Some((name.strip_prefix(&prefix)?.to_string(), content)))
+            .collect();
+    }
+    Ok(files)
+}
@@ -1,173 +1,190 @@
 /// Score: SQLite
-use chrono::Local;
-use rusqlite::{Connection, Result, params};
-use std::path::PathBuf;
+use crate::data_dir::{legacy_data_dir, migrate_legacy_file};
+use crate::error::{Error, Result};
+use crate::migration::migrate;
+use crate::score::{BOARD_SIZE, Board, ScoreEntry, ScoreRepository};
+use crate::utilities::get_data_path;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::path::{Path, PathBuf};
 
-/// Score data model
-///
-/// Corresponds to the `score` table in SQLite
-#[derive(Debug)]
-struct Score {
-    /// player name
-    name: String,
-    /// score
-    score: i32,
-    /// Creation timestamp (string format)
-    created_at: String,
-}
+/// File name of the score database
+pub const DB_FILE_NAME: &str = "flyrust.db";
+
+/// Personal bests kept per board, so a new name each round cannot grow
+/// the database without bound
+pub const KEPT_PERSONAL_BESTS: usize = 100;
+
+/// Columns of a score entry
+const ENTRY_COLUMNS: &str = "name, score, difficulty, mode, duration, app_version, created_at";
 
 /// My SQLite structure
+///
+/// Keeps the leaderboards in the `flyplayer` table, one row per score, and
+/// the best score of each player on each board in `personal_best`. A board
+/// keeps its best `BOARD_SIZE` scores and `KEPT_PERSONAL_BESTS` personal
+/// bests, the lower ones are dropped on insert.
 pub struct MyScore {
     pub conn: Connection,
-    pub name: String,
-    pub score: i32,
-    pub output: String,
 }
 impl MyScore {
     /// Create new MySQLite
-    pub fn new() -> rusqlite::Result<Self> {
-        let db = Self::db_dir("flyrust.db");
-        let conn = Connection::open(db)?;
-        Self::init_db(&conn)?;
+    ///
+    /// The database of an older version is copied first, a failed copy is
+    /// logged and the database is opened anyway.
+    pub fn new() -> Result<Self> {
+        let path = Self::default_path();
+        if let Err(e) = Self::migrate_legacy(&path) {
+            log::warn!("{e}");
+        }
+        Self::open(&path)
+    }
 
-        Ok(Self {
-            conn,
-            name: "Guest".to_string(),
-            score: 0,
-            output: "Hello, Fly and Rust.".to_string(),
-        })
+    /// The score database in the data directory
+    pub fn default_path() -> PathBuf {
+        get_data_path(DB_FILE_NAME)
     }
 
-    /// Find the parent directory of the current executable
-    /// and construct a full path to a database file located
-    /// in the same directory as the application binary.
-    ///
-    /// # Arguments
-    /// * `db_name` - SQLite database file name (e.g. `"flyplayer.db"`)
+    /// Copy the database left next to the app by an older version to `path`
     ///
     /// # Returns
-    /// A [`PathBuf`] pointing to:
-    /// `<AppExecutableDir>/<db_name>`
     ///
-    /// # Behavior
-    /// - Resolves the absolute path of the running executable
-    /// - Uses the executable's parent directory (not CWD)
-    /// - Appends `db_name` to that directory
+    /// * true when the database was copied
     ///
-    /// # Panics
-    /// - If the executable path cannot be determined
-    /// - If the executable has no parent directory
-    ///
-    /// # Example
-    /// ```no_run
-    /// let db_path = db_dir("flyplayer.db");
-    /// let conn = rusqlite::Connection::open(db_path)?;
-    /// ```
-    fn db_dir(db_name: &str) -> PathBuf {
-        let exe = std::env::current_exe().expect("Failed to get executable path");
-
-        // Windows: to open at the path is same with EXE
-        // exe.parent()
-        //     .expect("Failed to get executable directory")
-        //     .join(db_name)
-
-        // Mac: to open at the path is same with APP
-        exe.parent() // MacOS
-            .and_then(|p| p.parent()) // Contents
-            .and_then(|p| p.parent()) // FlyRust.app
-            .and_then(|p| p.parent()) // dist
-            .expect("Failed to get executable directory")
-            .join(db_name)
+    /// # Errors
+    /// - Returns `Error::Path` if the old database cannot be copied
+    pub fn migrate_legacy(path: &Path) -> Result<bool> {
+        let Some(old) = legacy_data_dir() else {
+            return Ok(false);
+        };
+        let old = old.join(DB_FILE_NAME);
+        migrate_legacy_file(&old, path).map_err(|e| Error::Path {
+            path: old,
+            reason: format!("cannot copy the scores to {}: {e}", path.display()),
+        })
     }
 
-    /// Initialize the SQLite database schema
-    ///
-    /// Creates the `score` table if it does not already exist.
+    /// Open the score database file, upgrading its schema
     ///
     /// # Errors
-    /// - Returns an error if the database write fails
-    fn init_db(conn: &Connection) -> Result<()> {
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS flyplayer (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            score INTEGER NOT NULL,
-            created_at TEXT NOT NULL
-        )",
-            [],
-        )?;
-        Ok(())
+    /// - Returns an error if the file cannot be opened or upgraded
+    /// - Returns an error if the database is newer than the game
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut conn = Connection::open(path)?;
+        migrate(&mut conn)?;
+        Ok(Self { conn })
     }
 
-    /// List all players
-    pub fn list(&mut self) {
-        let mut stmt = match self
-            .conn
-            .prepare("SELECT name, score, created_at FROM flyplayer ORDER BY score DESC;")
-        {
-            Ok(s) => s,
-            Err(e) => return self.output.push_str(&e.to_string()),
-        };
-
-        let players = match stmt.query_map([], |row| {
-            Ok(Score {
-                name: row.get("name")?,
-                score: row.get("score")?,
-                created_at: row.get("created_at")?,
-            })
-        }) {
-            Ok(s) => s,
-            Err(e) => return self.output.push_str(&e.to_string()),
-        };
-
-        // get ranking list
-        for (rank, player) in players.enumerate() {
-            let t = match player {
-                Ok(n) => n,
-                Err(e) => {
-                    return self.output.push_str(&format!("{}", e));
-                }
-            };
-
-            let ranking = rank + 1;
-
-            self.output.push_str(&format!(
-                "\n          {:^4}  {:>3}    {:^10}   {:^19}",
-                ranking, t.score, t.name, t.created_at
-            ));
-        }
+    // score entry of the row
+    fn entry(row: &Row) -> rusqlite::Result<ScoreEntry> {
+        Ok(ScoreEntry {
+            name: row.get("name")?,
+            score: row.get("score")?,
+            board: Board {
+                difficulty: row.get("difficulty")?,
+                mode: row.get("mode")?,
+            },
+            duration: row.get("duration")?,
+            app_version: row.get("app_version")?,
+            created_at: row.get("created_at")?,
+        })
     }
+}
 
-    /// Add a new player score
-    pub fn add(&mut self) {
-        // check input valid
-        let title = self.name.trim();
-        if title.is_empty() {
-            return;
-        }
-
-        let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+impl ScoreRepository for MyScore {
+    fn top(&self, board: &Board, limit: usize) -> Result<Vec<ScoreEntry>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {ENTRY_COLUMNS} FROM flyplayer
+            WHERE difficulty = ?1 AND mode = ?2
+            ORDER BY score DESC, id ASC LIMIT ?3"
+        ))?;
+        let entries = stmt
+            .query_map(
+                params![board.difficulty, board.mode, limit as i64],
+                Self::entry,
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(entries)
+    }
 
-        // INSERT
-        if let Err(e) = self.conn.execute(
-            "INSERT INTO flyplayer (name, score, created_at) VALUES (?1, ?2, ?3)",
-            params![title, self.score, now],
-        ) {
-            return self.output.push_str(&format!("❗ DB error: {}\n", e));
-        }
+    fn insert(&mut self, entry: &ScoreEntry) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            &format!("INSERT INTO flyplayer ({ENTRY_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"),
+            params![
+                entry.name,
+                entry.score,
+                entry.board.difficulty,
+                entry.board.mode,
+                entry.duration,
+                entry.app_version,
+                entry.created_at
+            ],
+        )?;
+        // an earlier best stays on a tie
+        tx.execute(
+            &format!(
+                "INSERT INTO personal_best ({ENTRY_COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT (name, difficulty, mode) DO UPDATE SET
+                    score = excluded.score,
+                    duration = excluded.duration,
+                    app_version = excluded.app_version,
+                    created_at = excluded.created_at
+                WHERE excluded.score > personal_best.score"
+            ),
+            params![
+                entry.name,
+                entry.score,
+                entry.board.difficulty,
+                entry.board.mode,
+                entry.duration,
+                entry.app_version,
+                entry.created_at
+            ],
+        )?;
+        // prune the board, ranked as in top
+        tx.execute(
+            "DELETE FROM flyplayer WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, ROW_NUMBER() OVER (ORDER BY score DESC, id ASC) AS board_rank
+                    FROM flyplayer
+                    WHERE difficulty = ?1 AND mode = ?2
+                )
+                WHERE board_rank > ?3
+            )",
+            params![entry.board.difficulty, entry.board.mode, BOARD_SIZE as i64],
+        )?;
+        // and its personal bests, the latest goes first on a tie
+        tx.execute(
+            "DELETE FROM personal_best WHERE rowid IN (
+                SELECT rowid FROM (
+                    SELECT rowid, ROW_NUMBER() OVER (ORDER BY score DESC, rowid ASC) AS best_rank
+                    FROM personal_best
+                    WHERE difficulty = ?1 AND mode = ?2
+                )
+                WHERE best_rank > ?3
+            )",
+            params![
+                entry.board.difficulty,
+                entry.board.mode,
+                KEPT_PERSONAL_BESTS as i64
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
 
-        // keep 10 players
-        let _ = self.conn.execute(
-            r#"
-            DELETE FROM flyplayer
-            WHERE id NOT IN (
-            SELECT id
-            FROM flyplayer
-            ORDER BY score DESC
-            LIMIT 10
-        )
-        "#,
-            [],
-        );
+    fn personal_best(&self, name: &str, board: &Board) -> Result<Option<ScoreEntry>> {
+        let entry = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT {ENTRY_COLUMNS} FROM personal_best
+                    WHERE name = ?1 AND difficulty = ?2 AND mode = ?3"
+                ),
+                params![name, board.difficulty, board.mode],
+                Self::entry,
+            )
+            .optional()?;
+        Ok(entry)
     }
 }
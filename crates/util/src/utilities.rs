@@ -1,7 +1,11 @@
 //! # utilities
 //!
 //! utilities functions
-use std::env;
+use std::path::PathBuf;
+
+use crate::data_dir::data_dir;
+use crate::error::{Error, Result};
+use crate::resource::{ResourceLocator, RootKind};
 
 /// what_panic logs the panic information, use `cat /tmp/what_panic.log` to check
 /// # Returns
@@ -9,7 +13,8 @@ use std::env;
 /// * Write panic information to /tmp/what_panic.log
 ///
 /// # Examples
-/// ```rust,ignore
+/// ```rust,no_run
+/// # use util::what_panic;
 /// what_panic();
 /// ```
 ///
@@ -38,6 +43,7 @@ pub fn what_panic() {
 ///
 /// # Examples
 /// ```
+/// use util::utilities::time_format_to_s_m;
 /// let time_string = time_format_to_s_m(10.1);
 /// assert_eq!(time_string, "10.1");
 /// ```
@@ -49,9 +55,8 @@ pub fn time_format_to_s_m(total_seconds: f32) -> String {
 }
 
 /// Get absolute path of the resource files
-/// Place assets to Workspace/Contents/Resources
+/// Look up the file on disk in the resource roots, see [`crate::resource`]
 /// Keep consistent in debug mode, release mode and macOS app package
-/// Read from ./Contents/Resources
 ///
 /// # Arguments
 ///
@@ -61,27 +66,60 @@ pub fn time_format_to_s_m(total_seconds: f32) -> String {
 ///
 /// *  String - patch to absolute path
 ///
+/// # Errors
+/// - Returns `Error::Path` if no resource root has the file on disk
+/// - Returns `Error::Path` if the path is not UTF-8
+///
 /// # Examples
 /// ```rust,no_run
-/// let path = &get_resource_path_str("assets/images/logo.png");
+/// use util::get_resource_path_str;
+/// let path = &get_resource_path_str("assets/i18n/ui.json").unwrap();
 /// ```
 ///
-pub fn get_resource_path_str(relative_path: &str) -> String {
-    let exe_path = env::current_exe().expect("Read failed!");
-    // get the app root
-    let app_root = exe_path
-        .ancestors()
-        .nth(3)
-        .expect("Failed to find .app root");
-    // set Contents/Resources
-    let resources_dir = app_root.join("Contents").join("Resources");
+pub fn get_resource_path_str(relative_path: &str) -> Result<String> {
+    let locator = ResourceLocator::new();
+    let disk = ResourceLocator::with_roots(
+        locator
+            .roots()
+            .iter()
+            .filter(|root| root.kind() != RootKind::Embedded)
+            .cloned()
+            .collect(),
+    );
+    let full_path = disk.require(relative_path)?.path().unwrap_or_default();
+    let full_path = full_path.canonicalize().unwrap_or(full_path);
+    match full_path.to_str() {
+        Some(path) => Ok(path.to_string()),
+        None => Err(Error::Path {
+            path: full_path,
+            reason: "not UTF-8".to_string(),
+        }),
+    }
+}
 
-    // patch the input
-    let full_path = resources_dir.join(relative_path);
-    full_path
-        .canonicalize()
-        .unwrap_or(full_path)
-        .to_str()
-        .expect("Not UTF-8")
-        .to_string()
+/// Construct a full path to a data file in the data directory
+///
+/// The data directory is created when missing, see [`crate::data_dir`] for
+/// where it is. A directory that cannot be created is logged as a warning.
+///
+/// # Arguments
+/// * `file_name` - data file or folder name (e.g. `"flyrust.db"`)
+///
+/// # Returns
+/// A [`PathBuf`] pointing to:
+/// `<DataDir>/<file_name>`
+///
+/// # Example
+/// ```no_run
+/// use util::get_data_path;
+/// let db_path = get_data_path("flyrust.db");
+/// let conn = rusqlite::Connection::open(db_path);
+/// ```
+pub fn get_data_path(file_name: &str) -> PathBuf {
+    let dir = data_dir();
+    // opening the file reports the failure, log the cause
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::warn!("cannot create the data directory {}: {e}", dir.display());
+    }
+    dir.join(file_name)
 }
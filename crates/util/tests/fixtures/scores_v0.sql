@@ -0,0 +1,11 @@
+-- score database before the boards, no schema version
+CREATE TABLE flyplayer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+INSERT INTO flyplayer (name, score, created_at) VALUES
+    ('Ferris', 12, '2026-01-02 10:00:00'),
+    ('Guest', 7, '2026-01-02 10:05:00'),
+    ('Crab', 9, '2026-01-03 18:30:00');
@@ -0,0 +1,11 @@
+-- schema version 1: the score table
+CREATE TABLE flyplayer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+INSERT INTO flyplayer (name, score, created_at) VALUES
+    ('Ferris', 12, '2026-01-02 10:00:00'),
+    ('Crab', 9, '2026-01-03 18:30:00');
+PRAGMA user_version = 1;
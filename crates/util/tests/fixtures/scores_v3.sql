@@ -0,0 +1,29 @@
+-- schema version 3: personal bests in their own table
+CREATE TABLE flyplayer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    difficulty TEXT NOT NULL DEFAULT 'Easy',
+    mode TEXT NOT NULL DEFAULT 'Classic',
+    duration REAL NOT NULL DEFAULT 10.0,
+    app_version TEXT NOT NULL DEFAULT ''
+);
+CREATE INDEX flyplayer_board ON flyplayer (difficulty, mode, score DESC);
+CREATE TABLE personal_best (
+    name TEXT NOT NULL,
+    score INTEGER NOT NULL,
+    difficulty TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    duration REAL NOT NULL,
+    app_version TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (name, difficulty, mode)
+);
+INSERT INTO flyplayer (name, score, created_at, difficulty, mode, duration, app_version) VALUES
+    ('Ferris', 12, '2026-01-02 10:00:00', 'Easy', 'Classic', 10.0, '0.1.0'),
+    ('Hopper', 3, '2026-02-10 21:00:00', 'Hard', 'Classic', 10.0, '0.1.0');
+INSERT INTO personal_best (name, score, difficulty, mode, duration, app_version, created_at) VALUES
+    ('Ferris', 12, 'Easy', 'Classic', 10.0, '0.1.0', '2026-01-02 10:00:00'),
+    ('Hopper', 3, 'Hard', 'Classic', 10.0, '0.1.0', '2026-02-10 21:00:00');
+PRAGMA user_version = 3;
@@ -0,0 +1,28 @@
+# French labels of FlyRust, a partial translation
+-brand = Mouche Rust
+
+locale =
+    .code = fr
+    .name = Français
+    .fallback = en
+
+app_name = { -brand }
+
+popup =
+    .game_over = Fin de partie
+    .ok = D'accord
+
+gameover =
+    .title = { popup.game_over }
+    .info = { $score ->
+        [0] RIEN ATTRAPÉ !
+        [one] OUPS, { $score } seul Rustacean.
+       *[other] Vous avez attrapé { $score } Rustaceans.
+    }
+    .close_title = { DATETIME($now) }
+
+storage =
+    .exported = { NUMBER($count) } { $count ->
+        [one] score exporté
+       *[other] scores exportés
+    } vers { $path }
@@ -1,7 +1,947 @@
-// crates/lib/tests/integration_test.rs
-// use util::add;
+// crates/util/tests/integration_test.rs
+use eframe::egui;
+use rusqlite::Connection;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use util::asset_manifest::AssetManifest;
+use util::assets::{ASSETS, MyAssets, UiConfig};
+use util::customfonts::{FontLibrary, FontManifest};
+use util::data_dir::{migrate_legacy_file, resolve_data_dir};
+use util::fluent::ftl_to_value;
+use util::font_check::{check_coverage, check_installed};
+use util::i18n::{BUILT_IN, I18nSource, KeyIssue, discover_locales, fallback_chain, find_locale};
+use util::i18n_check::{
+    Finding, I18N_BUNDLE_DIR, I18N_SOURCE_DIR, assert_translations_complete, check_workspace,
+};
+use util::message::{Args, Message, format_message, format_number};
+use util::migration::{SCHEMA_VERSION, migrate, schema_version};
+use util::pseudo::{PSEUDO_LOCALE, pseudo_text, pseudo_value};
+use util::resource::{ResourceLocator, ResourceRoot, RootKind};
+use util::score::{BOARD_SIZE, Board, ScoreEntry, ScoreStore, StorageError};
+use util::settings::Settings;
+use util::skin::{DEFAULT_FLY_SIZE, Look, Skin, discover_skins};
+use util::sqlite::KEPT_PERSONAL_BESTS;
+use util::{Error, MemoryScore, MyScore, ScoreRepository};
 
-// #[test]
-// fn it_adds_one() {
-//     assert_eq!(add(1, 2), 3);
-// }
+// a database file built from the fixture script, in a fresh temp dir
+fn fixture_db(test: &str, fixture: Option<&str>) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("flyrust-test-{}-{}", std::process::id(), test));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("flyrust.db");
+    if let Some(fixture) = fixture {
+        let sql = std::fs::read_to_string(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/fixtures")
+                .join(fixture),
+        )
+        .unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(&sql)
+            .unwrap();
+    }
+    path
+}
+
+// names on the board, best first
+fn board(scores: &dyn ScoreRepository, difficulty: &str, mode: &str) -> Vec<String> {
+    scores
+        .top(&Board::new(difficulty, mode), 10)
+        .unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect()
+}
+
+// the same rounds on both backends
+fn play_rounds(scores: &mut dyn ScoreRepository) {
+    let easy = Board::new("Easy", "Classic");
+    let hard = Board::new("Hard", "Classic");
+    for (name, score, board) in [
+        ("Ferris", 12, &easy),
+        ("Crab", 15, &easy),
+        ("Ferris", 15, &easy),
+        ("Ferris", 20, &hard),
+        ("Guest", 3, &easy),
+    ] {
+        scores
+            .insert(&ScoreEntry::new(name, score, board.clone(), 10.0, "1.2.3"))
+            .unwrap();
+    }
+}
+
+// rankings the rounds of play_rounds give
+fn check_rankings(scores: &dyn ScoreRepository) {
+    let easy = Board::new("Easy", "Classic");
+    assert_eq!(
+        board(scores, "Easy", "Classic"),
+        ["Crab", "Ferris", "Ferris", "Guest"]
+    );
+    assert_eq!(board(scores, "Hard", "Classic"), ["Ferris"]);
+    assert!(board(scores, "Medium", "Classic").is_empty());
+
+    let top = scores.top(&easy, 2).unwrap();
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].score, 15);
+    assert_eq!(top[0].app_version, "1.2.3");
+
+    let best = scores.personal_best("Ferris", &easy).unwrap().unwrap();
+    assert_eq!(best.score, 15);
+    assert_eq!(best.board, easy);
+    assert!(scores.personal_best("Nobody", &easy).unwrap().is_none());
+}
+
+#[test]
+fn memory_scores_rank_the_boards() {
+    let mut scores = MemoryScore::default();
+    play_rounds(&mut scores);
+    check_rankings(&scores);
+}
+
+#[test]
+fn sqlite_scores_rank_the_boards() {
+    let path = fixture_db("rank", None);
+    let mut scores = MyScore::open(&path).unwrap();
+    play_rounds(&mut scores);
+    check_rankings(&scores);
+
+    // and keep them
+    drop(scores);
+    check_rankings(&MyScore::open(&path).unwrap());
+}
+
+// rows of the table on the board of the difficulty
+fn rows(scores: &MyScore, table: &str, difficulty: &str) -> i64 {
+    scores
+        .conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM {table} WHERE difficulty = ?1"),
+            [difficulty],
+            |row| row.get(0),
+        )
+        .unwrap()
+}
+
+#[test]
+fn sqlite_boards_keep_the_top_and_each_players_best() {
+    let path = fixture_db("prune", None);
+    let mut scores = MyScore::open(&path).unwrap();
+    let easy = Board::new("Easy", "Classic");
+    let hard = Board::new("Hard", "Classic");
+    scores
+        .insert(&ScoreEntry::new("Guest", 1, easy.clone(), 10.0, "1.2.3"))
+        .unwrap();
+    scores
+        .insert(&ScoreEntry::new("Crab", 2, hard.clone(), 10.0, "1.2.3"))
+        .unwrap();
+    for score in 0..3 * BOARD_SIZE as i32 {
+        scores
+            .insert(&ScoreEntry::new(
+                "Ferris",
+                10 + score,
+                easy.clone(),
+                10.0,
+                "1.2.3",
+            ))
+            .unwrap();
+    }
+
+    assert_eq!(rows(&scores, "flyplayer", "Easy"), BOARD_SIZE as i64);
+    let top = scores.top(&easy, BOARD_SIZE).unwrap();
+    assert_eq!(top[0].score, 9 + 3 * BOARD_SIZE as i32);
+    assert_eq!(top.len(), BOARD_SIZE);
+
+    // off the board, the best of a player is kept, and the other boards too
+    assert_eq!(
+        scores.personal_best("Guest", &easy).unwrap().unwrap().score,
+        1
+    );
+    assert_eq!(board(&scores, "Hard", "Classic"), ["Crab"]);
+}
+
+#[test]
+fn sqlite_tables_stay_bounded_with_a_new_name_each_round() {
+    let path = fixture_db("names", None);
+    let mut scores = MyScore::open(&path).unwrap();
+    let easy = Board::new("Easy", "Classic");
+    let players = KEPT_PERSONAL_BESTS + BOARD_SIZE;
+    for n in 0..players {
+        let entry = ScoreEntry::new(&format!("Player{n}"), n as i32, easy.clone(), 10.0, "1.2.3");
+        scores.insert(&entry).unwrap();
+    }
+    assert_eq!(rows(&scores, "flyplayer", "Easy"), BOARD_SIZE as i64);
+    assert_eq!(
+        rows(&scores, "personal_best", "Easy"),
+        KEPT_PERSONAL_BESTS as i64
+    );
+
+    // the lowest personal bests went first
+    let last = format!("Player{}", players - 1);
+    assert_eq!(
+        scores.personal_best(&last, &easy).unwrap().unwrap().score,
+        players as i32 - 1
+    );
+    assert!(scores.personal_best("Player0", &easy).unwrap().is_none());
+
+    // a lower score keeps the best
+    scores
+        .insert(&ScoreEntry::new(&last, 0, easy.clone(), 10.0, "1.2.3"))
+        .unwrap();
+    assert_eq!(
+        scores.personal_best(&last, &easy).unwrap().unwrap().score,
+        players as i32 - 1
+    );
+}
+
+#[test]
+fn new_database_gets_the_latest_schema() {
+    let path = fixture_db("new", None);
+    let score = MyScore::open(&path).unwrap();
+    assert_eq!(schema_version(&score.conn).unwrap(), SCHEMA_VERSION);
+    assert!(board(&score, "Easy", "Classic").is_empty());
+}
+
+#[test]
+fn every_past_version_upgrades() {
+    for (n, fixture) in [
+        "scores_v0.sql",
+        "scores_v0_boards.sql",
+        "scores_v1.sql",
+        "scores_v2.sql",
+        "scores_v3.sql",
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let path = fixture_db(&format!("upgrade{n}"), Some(fixture));
+        let mut score = MyScore::open(&path).unwrap_or_else(|e| panic!("{fixture}: {e}"));
+        assert_eq!(
+            schema_version(&score.conn).unwrap(),
+            SCHEMA_VERSION,
+            "{fixture}"
+        );
+
+        // the old scores are kept, on the Easy Classic board
+        let easy = board(&score, "Easy", "Classic");
+        assert_eq!(easy[0], "Ferris", "{fixture}");
+        let best = score
+            .personal_best("Ferris", &Board::new("Easy", "Classic"))
+            .unwrap();
+        assert_eq!(best.map(|e| e.score), Some(12), "{fixture}");
+
+        // and the upgraded database takes new scores
+        let medium = Board::new("Medium", "Classic");
+        score
+            .insert(&ScoreEntry::new("New", 5, medium.clone(), 10.0, "1.2.3"))
+            .unwrap();
+        assert_eq!(board(&score, "Medium", "Classic"), ["New"], "{fixture}");
+    }
+}
+
+#[test]
+fn boards_of_an_unversioned_database_are_kept() {
+    let path = fixture_db("boards", Some("scores_v0_boards.sql"));
+    let score = MyScore::open(&path).unwrap();
+    assert_eq!(board(&score, "Hard", "Classic"), ["Grace", "Hopper"]);
+    assert_eq!(board(&score, "Easy", "Classic"), ["Ferris"]);
+}
+
+#[test]
+fn migrating_twice_changes_nothing() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    assert_eq!(migrate(&mut conn).unwrap(), 0);
+    assert_eq!(migrate(&mut conn).unwrap(), SCHEMA_VERSION);
+}
+
+#[test]
+fn newer_database_is_refused() {
+    let path = fixture_db("newer", Some("scores_v1.sql"));
+    {
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+    }
+
+    match MyScore::open(&path) {
+        Err(Error::Storage(StorageError::TooNew { found, supported })) => {
+            assert_eq!(found, SCHEMA_VERSION + 1);
+            assert_eq!(supported, SCHEMA_VERSION);
+        }
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("a newer database was opened"),
+    }
+
+    // left as it was
+    let conn = Connection::open(&path).unwrap();
+    assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION + 1);
+    let columns: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('flyplayer')",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(columns, 4);
+}
+
+#[test]
+fn missing_translation_is_an_i18n_error() {
+    match UiConfig::load("no/such/ui.json") {
+        Err(Error::I18n { path, .. }) => assert_eq!(path, "no/such/ui.json"),
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("a missing file was loaded"),
+    }
+}
+
+#[test]
+fn bundled_assets_load_and_missing_ones_fail() {
+    let ctx = egui::Context::default();
+    let manifest = AssetManifest::embedded().unwrap();
+    assert!(manifest.validate(&ASSETS).is_empty());
+    // the bundled fonts are checked like the textures
+    assert_eq!(manifest.font("UnifontCJK"), Some("fonts/unifont-cjk.ttf"));
+    let assets = MyAssets::load_from_json(&ctx).unwrap();
+    for texture in &manifest.textures {
+        assert!(assets.get(&texture.id).is_some(), "{}", texture.id);
+    }
+    match MyAssets::load_texture(&ctx, "Nothing", "image/nothing.png") {
+        Err(Error::Asset { path, .. }) => assert_eq!(path, "image/nothing.png"),
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("a missing asset was loaded"),
+    }
+}
+
+#[test]
+fn session_scores_move_to_the_database_when_it_comes_back() {
+    let dir = fixture_db("session", None).with_file_name("missing");
+    let path = dir.join("flyrust.db");
+    let easy = Board::new("Easy", "Classic");
+
+    // no directory for the database yet, the scores stay in the session
+    let mut store = ScoreStore::sqlite(path.clone());
+    assert!(store.is_session_only());
+    assert!(store.error().is_some());
+    store
+        .insert(&ScoreEntry::new("Ferris", 12, easy.clone(), 10.0, "1.2.3"))
+        .unwrap();
+    assert_eq!(board(&store, "Easy", "Classic"), ["Ferris"]);
+    assert!(store.retry().is_err());
+    assert_eq!(store.pending().len(), 1);
+
+    // export the session meanwhile, to the first directory that works
+    let fallback = dir.parent().unwrap().to_path_buf();
+    let (export, count) = store
+        .export_to(&[dir.clone(), fallback.clone()], "scores.json")
+        .unwrap();
+    assert_eq!((export.clone(), count), (fallback.join("scores.json"), 1));
+    assert!(matches!(
+        store.export_to(std::slice::from_ref(&dir), "scores.json"),
+        Err(Error::Path { .. })
+    ));
+    let exported: Vec<ScoreEntry> =
+        serde_json::from_str(&std::fs::read_to_string(&export).unwrap()).unwrap();
+    assert_eq!(exported[0].name, "Ferris");
+
+    // the storage comes back
+    std::fs::create_dir_all(&dir).unwrap();
+    store.retry().unwrap();
+    assert!(!store.is_session_only());
+    assert!(store.pending().is_empty());
+    assert_eq!(
+        board(&MyScore::open(&path).unwrap(), "Easy", "Classic"),
+        ["Ferris"]
+    );
+}
+
+#[test]
+fn data_dir_prefers_the_override_then_the_environment() {
+    let platform = Some(PathBuf::from("/home/ferris/.local/share/flyrust"));
+    assert_eq!(
+        resolve_data_dir(None, None, platform.clone()),
+        PathBuf::from("/home/ferris/.local/share/flyrust")
+    );
+    assert_eq!(
+        resolve_data_dir(None, Some("".into()), platform.clone()),
+        PathBuf::from("/home/ferris/.local/share/flyrust")
+    );
+    assert_eq!(
+        resolve_data_dir(None, Some("/env".into()), platform.clone()),
+        PathBuf::from("/env")
+    );
+    assert_eq!(
+        resolve_data_dir(Some(Path::new("/cli")), Some("/env".into()), platform),
+        PathBuf::from("/cli")
+    );
+}
+
+#[test]
+fn old_database_is_copied_to_the_data_dir() {
+    let old = fixture_db("legacy", Some("scores_v0.sql"));
+    let new = old.with_file_name("data").join("flyrust.db");
+
+    assert!(migrate_legacy_file(&old, &new).unwrap());
+    assert!(old.exists());
+    assert_eq!(
+        board(&MyScore::open(&new).unwrap(), "Easy", "Classic")[0],
+        "Ferris"
+    );
+
+    // a database already in the data dir is kept
+    assert!(!migrate_legacy_file(&old, &new).unwrap());
+    assert!(!migrate_legacy_file(&old.with_file_name("none.db"), &new).unwrap());
+
+    // a copy that cannot be written is an error, not an empty board
+    assert!(migrate_legacy_file(&old, &old.join("flyrust.db")).is_err());
+}
+
+#[test]
+fn resources_come_from_the_first_root_that_has_them() {
+    let dir = fixture_db("resources", None).with_file_name("root");
+    std::fs::create_dir_all(dir.join("assets/i18n")).unwrap();
+    std::fs::write(dir.join("assets/i18n/ui.json"), "{}").unwrap();
+    let embedded = ResourceLocator::new().roots().last().cloned().unwrap();
+    let locator = ResourceLocator::with_roots(vec![
+        ResourceRoot::Dir {
+            kind: RootKind::Env,
+            dir: dir.with_file_name("empty"),
+        },
+        ResourceRoot::Dir {
+            kind: RootKind::ExeDir,
+            dir: dir.clone(),
+        },
+        embedded,
+    ]);
+
+    let ui = locator.locate("assets/i18n/ui.json").unwrap();
+    assert_eq!(ui.root.kind(), RootKind::ExeDir);
+    assert_eq!(ui.read_to_string().unwrap(), "{}");
+
+    let hand = locator.locate("assets/image/hand.png").unwrap();
+    assert_eq!(hand.root.kind(), RootKind::Embedded);
+    assert!(hand.path().is_none());
+    assert!(!hand.read().unwrap().is_empty());
+
+    match locator.require("assets/i18n/ui_xx.json") {
+        Err(Error::Path { reason, .. }) => assert!(reason.contains("Embedded"), "{reason}"),
+        _ => panic!("a missing resource was found"),
+    }
+}
+
+#[test]
+fn translations_are_embedded() {
+    for code in ["en", "zh-TW", "zh-CN"] {
+        let (ui, source) = UiConfig::load_locale(code).unwrap();
+        assert_eq!(source, I18nSource::Embedded, "{code}");
+        assert_eq!(ui.locale.code, code);
+    }
+    assert!(matches!(
+        UiConfig::load_locale("xx"),
+        Err(Error::I18n { .. })
+    ));
+}
+
+#[test]
+fn missing_keys_fall_back_per_key() {
+    let layers = [
+        (
+            "zh-TW".to_string(),
+            json!({
+                "locale": { "code": "zh-TW", "name": "繁體中文", "fallback": "zh-CN" },
+                "popup": { "ok": "確定" },
+                "gameover": { "titel": "遊戲結束" }
+            }),
+        ),
+        (
+            "zh-CN".to_string(),
+            json!({ "popup": { "game_over": "游戏结束" } }),
+        ),
+        ("en".to_string(), json!({ "labels": { "start": "Go" } })),
+    ];
+    let (ui, issues) = UiConfig::resolve(&layers).unwrap();
+    assert_eq!(ui.locale.code, "zh-TW");
+    assert_eq!(ui.popup.ok, "確定");
+    assert_eq!(ui.popup.game_over, "游戏结束");
+    assert_eq!(ui.labels.start, "Go");
+    assert_eq!(ui.gameover.title, UiConfig::default().gameover.title);
+
+    let missing = |key: &str, from: &str| KeyIssue::Missing {
+        source: "zh-TW".to_string(),
+        key: key.to_string(),
+        from: from.to_string(),
+    };
+    assert!(issues.contains(&missing("popup.game_over", "zh-CN")));
+    assert!(issues.contains(&missing("labels.start", "en")));
+    assert!(issues.contains(&missing("gameover.title", BUILT_IN)));
+    assert!(
+        !issues
+            .iter()
+            .any(|i| matches!(i, KeyIssue::Missing { key, .. } if key == "popup.ok"))
+    );
+    assert!(issues.contains(&KeyIssue::Unknown {
+        source: "zh-TW".to_string(),
+        key: "gameover.titel".to_string(),
+    }));
+}
+
+#[test]
+fn game_over_message_follows_the_plural_rules() {
+    let score = |code: &str, n: u32| {
+        let (ui, _) = UiConfig::load_locale(code).unwrap();
+        ui.format(&ui.gameover.info, &Args::new().with("score", n))
+    };
+    assert_eq!(score("en", 0), "BAD! YOU GOT NOTHING!");
+    assert_eq!(score("en", 1), "OOPS, you got ONLY 1.");
+    assert_eq!(score("en", 1200), "You got 1,200 Rustaceans.");
+    assert_eq!(score("zh-TW", 1), "可惜了，你只抓到 1 隻。");
+    assert_eq!(score("zh-CN", 7), "你抓到了 7 只。");
+}
+
+#[test]
+fn messages_format_numbers_and_selects() {
+    let args = Args::new()
+        .with("n", 2)
+        .with("rate", 0.25)
+        .with("difficulty", "hard");
+    let format = |template: &str| format_message("fr", template, &args);
+    assert_eq!(
+        format("{n, plural, one {# mouche} other {# mouches}}"),
+        "2 mouches"
+    );
+    assert_eq!(format("{rate, number, percent}"), "25%");
+    assert_eq!(
+        format("{difficulty, select, hard {⭐⭐⭐} other {⭐}}"),
+        "⭐⭐⭐"
+    );
+    assert_eq!(format("{missing}"), "{missing}");
+    assert_eq!(format_number("fr", 1234.5), "1\u{a0}234,5");
+
+    for bad in ["{n", "n}", "{n, plural, one {#}}", "{n, date}"] {
+        assert!(
+            matches!(Message::parse(bad), Err(Error::Message { .. })),
+            "{bad}"
+        );
+    }
+}
+
+#[test]
+fn translations_with_unknown_placeholders_fall_back() {
+    let layers = [
+        (
+            "zh-TW".to_string(),
+            json!({ "gameover": { "info": "你抓到了 {scor} 隻。" }, "popup": { "ok": "{ok" } }),
+        ),
+        (
+            "zh-CN".to_string(),
+            json!({ "gameover": { "info": "你抓到了 {score} 只。" } }),
+        ),
+    ];
+    let (ui, issues) = UiConfig::resolve(&layers).unwrap();
+    assert_eq!(ui.gameover.info, "你抓到了 {score} 只。");
+    assert_eq!(ui.popup.ok, UiConfig::default().popup.ok);
+    assert!(issues.contains(&KeyIssue::Placeholder {
+        source: "zh-TW".to_string(),
+        key: "gameover.info".to_string(),
+        name: "scor".to_string(),
+    }));
+    assert!(
+        issues
+            .iter()
+            .any(|i| matches!(i, KeyIssue::Syntax { key, .. } if key == "popup.ok"))
+    );
+}
+
+#[test]
+fn fluent_translations_give_the_same_labels() {
+    let ftl = include_str!("fixtures/ui_fr.ftl");
+    let (layer, issues) = ftl_to_value(ftl, "ui_fr.ftl").unwrap();
+    assert!(issues.contains(&KeyIssue::Syntax {
+        source: "ui_fr.ftl".to_string(),
+        key: "gameover.close_title".to_string(),
+        reason: "uses the unsupported function DATETIME()".to_string(),
+    }));
+
+    let (en, _) = find_locale("en").unwrap().load().unwrap();
+    let layers = [("fr".to_string(), layer), ("en".to_string(), en)];
+    let (ui, issues) = UiConfig::resolve(&layers).unwrap();
+    assert_eq!(ui.locale.code, "fr");
+    assert_eq!(ui.app_name, "Mouche Rust");
+    assert_eq!(ui.popup.ok, "D'accord");
+    assert_eq!(ui.gameover.title, "Fin de partie");
+    assert_eq!(ui.gameover.close_title, "ByeBye");
+    assert!(issues.contains(&KeyIssue::Missing {
+        source: "fr".to_string(),
+        key: "gameover.close_title".to_string(),
+        from: "en".to_string(),
+    }));
+
+    let score = |n: u32| ui.format(&ui.gameover.info, &Args::new().with("score", n));
+    assert_eq!(score(0), "RIEN ATTRAPÉ !");
+    assert_eq!(score(1), "OUPS, 1 seul Rustacean.");
+    assert_eq!(score(1500), "Vous avez attrapé 1\u{a0}500 Rustaceans.");
+    let args = Args::new().with("count", 2).with("path", "/tmp/x.json");
+    assert_eq!(
+        ui.format(&ui.storage.exported, &args),
+        "2 scores exportés vers /tmp/x.json"
+    );
+
+    match UiConfig::from_ftl("popup =\n    .ok = {\n", "broken.ftl") {
+        Err(Error::I18n { path, reason }) => {
+            assert_eq!(path, "broken.ftl");
+            assert!(reason.contains("line"), "{reason}");
+        }
+        _ => panic!("a broken Fluent file was loaded"),
+    }
+}
+
+#[test]
+fn workspace_translations_are_complete() {
+    assert_translations_complete(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../.."));
+}
+
+#[test]
+fn checker_reports_incomplete_translations_and_drift() {
+    let root = std::env::temp_dir().join(format!("flyrust-test-{}-i18n", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let (source, bundle) = (root.join(I18N_SOURCE_DIR), root.join(I18N_BUNDLE_DIR));
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::create_dir_all(&bundle).unwrap();
+    let en = r#"{ "locale": { "code": "en", "name": "English" },
+        "popup": { "ok": "OK", "game_over": "Game Over" },
+        "storage": { "exported": "Exported {count} scores to {path}" } }"#;
+    let fr = r#"{ "locale": { "code": "fr", "name": "Français" },
+        "popup": { "ok": "OK", "quit": "Quitter" },
+        "storage": { "exported": "{count} scores exportés vers {chemin}" } }"#;
+    for (name, data) in [("ui.json", en), ("ui_fr.json", fr)] {
+        std::fs::write(source.join(name), data).unwrap();
+    }
+    std::fs::write(bundle.join("ui.json"), en).unwrap();
+
+    let report = check_workspace(&root).unwrap();
+    let s = |text: &str| text.to_string();
+    let expected = [
+        Finding::Missing {
+            locale: s("fr"),
+            key: s("popup.game_over"),
+        },
+        Finding::Extra {
+            locale: s("fr"),
+            key: s("popup.quit"),
+        },
+        Finding::Untranslated {
+            locale: s("fr"),
+            key: s("popup.ok"),
+        },
+        Finding::Placeholder {
+            locale: s("fr"),
+            key: s("storage.exported"),
+            missing: vec![s("path")],
+            extra: vec![s("chemin")],
+        },
+        Finding::Drift {
+            file_name: s("ui_fr.json"),
+            source: source.join("ui_fr.json"),
+            copy: bundle.join("ui_fr.json"),
+        },
+    ];
+    for finding in &expected {
+        assert!(report.findings.contains(finding), "{finding}\n{report}");
+    }
+    assert_eq!(report.findings.len(), expected.len(), "{report}");
+}
+
+#[test]
+fn pseudo_locale_pads_every_label_and_keeps_placeholders() {
+    let (english, _) = UiConfig::load_locale("en").unwrap();
+    let (pseudo, source) = UiConfig::load_locale(PSEUDO_LOCALE).unwrap();
+    assert_eq!(source, I18nSource::Generated);
+    assert_eq!(pseudo.locale.code, PSEUDO_LOCALE);
+    assert_eq!(pseudo.popup.game_over, "[Ĝámé Övéŕ ~~~~]");
+
+    for (english, pseudo) in [
+        (&english.labels.start, &pseudo.labels.start),
+        (&english.gameover.close_bye, &pseudo.gameover.close_bye),
+    ] {
+        assert!(pseudo.starts_with('[') && pseudo.ends_with(']'), "{pseudo}");
+        assert!(pseudo.chars().count() > english.chars().count(), "{pseudo}");
+    }
+    let args = Args::new().with("score", 3);
+    let info = pseudo.format(&pseudo.gameover.info, &args);
+    assert!(info.starts_with("[Ýöû ĝöţ 3 Ŕûšţáçéáñš. ~"), "{info}");
+    assert!(info.ends_with("~]"), "{info}");
+    assert_eq!(pseudo_text("OK"), "[ÖĶ ~]");
+}
+
+#[test]
+fn embedded_locales_resolve_through_their_chain() {
+    let codes: Vec<String> = fallback_chain("zh-TW")
+        .into_iter()
+        .map(|l| l.language.code)
+        .collect();
+    assert_eq!(codes, ["zh-TW", "zh-CN", "en"]);
+    assert!(fallback_chain("xx").is_empty());
+
+    let (ui, _) = UiConfig::load_locale("zh-TW").unwrap();
+    assert_eq!(ui.locale.fallback.as_deref(), Some("zh-CN"));
+}
+
+#[test]
+fn locales_are_discovered() {
+    let locales = discover_locales();
+    let codes: Vec<&str> = locales.iter().map(|l| l.language.code.as_str()).collect();
+    assert_eq!(codes, ["en", "zh-CN", "zh-TW"]);
+
+    let info = find_locale("zh-TW").unwrap();
+    assert_eq!(info.file_name, "ui_zh-TW.json");
+    assert!(info.read().unwrap().contains("繁體中文"));
+    assert!(find_locale("xx").is_none());
+}
+
+#[test]
+fn font_manifest_gives_each_locale_its_chain() {
+    let fonts = FontLibrary::load().unwrap();
+    assert_eq!(fonts.origin("Hack"), Some("egui"));
+    // only the CJK faces depend on the machine
+    for (id, reason) in fonts.unavailable() {
+        assert!(id.starts_with("SystemCJK_"), "{id}: {reason}");
+    }
+    // the bundled CJK subset renders the translations without them
+    let origin = fonts.origin("UnifontCJK").unwrap();
+    assert!(
+        origin.starts_with("assets/fonts/unifont-cjk.ttf"),
+        "{origin}"
+    );
+    let report = check_installed(&[]).unwrap();
+    assert!(report.is_clean(), "{report}");
+
+    let english = fonts.chain("en");
+    assert_eq!(english[0], "Ubuntu-Light");
+    assert_eq!(fonts.chain(PSEUDO_LOCALE), english);
+    assert_eq!(fonts.chain("fr"), english);
+
+    let chinese = fonts.chain("zh-TW");
+    if fonts.origin("SystemCJK_TC").is_some() {
+        assert_eq!(chinese[0], "SystemCJK_TC");
+    }
+    let definitions = fonts.definitions("zh-TW");
+    assert_eq!(
+        definitions.families[&egui::FontFamily::Proportional],
+        chinese
+    );
+    let monospace = &definitions.families[&egui::FontFamily::Monospace];
+    assert_eq!(monospace[0], "Hack");
+    assert!(chinese.iter().all(|id| monospace.contains(id)));
+
+    let unknown = r#"{ "faces": [], "locales": { "en": ["Hack"] } }"#;
+    assert!(matches!(
+        FontManifest::from_json(unknown, "fonts.json"),
+        Err(Error::Asset { .. })
+    ));
+}
+
+#[test]
+fn coverage_report_flags_the_glyphs_no_font_has() {
+    let manifest = r#"{
+        "faces": [{ "id": "Ubuntu-Light" }, { "id": "NotoEmoji-Regular" }, { "id": "Hack" }],
+        "monospace": ["Hack"],
+        "locales": { "en": ["Ubuntu-Light", "NotoEmoji-Regular"] }
+    }"#;
+    let fonts =
+        FontLibrary::from_manifest(FontManifest::from_json(manifest, "fonts.json").unwrap());
+    assert!(fonts.unavailable().is_empty());
+
+    let english = find_locale("en").unwrap().load().unwrap().0;
+    let pseudo = pseudo_value(&english);
+    let symbols = [
+        ("icon.warning", "❗"),
+        ("icon.info", "🔔"),
+        ("icon.trophy", "🏆"),
+    ];
+    let latin = [
+        ("en".to_string(), english),
+        (PSEUDO_LOCALE.to_string(), pseudo),
+    ];
+    let report = check_coverage(&fonts, &latin, &symbols);
+    assert!(report.is_clean(), "{report}");
+
+    let chinese = find_locale("zh-TW").unwrap().load().unwrap().0;
+    let report = check_coverage(&fonts, &[("zh-TW".to_string(), chinese)], &symbols);
+    assert!(!report.is_clean());
+    let title = report
+        .gaps
+        .iter()
+        .find(|gap| gap.key == "gameover.title")
+        .unwrap();
+    assert_eq!(title.locale, "zh-TW");
+    assert!(title.chars.contains(&'戲'), "{report}");
+    assert!(report.gaps.iter().any(|gap| gap.key == "language zh-TW"));
+    assert!(report.gaps.iter().all(|gap| !gap.key.starts_with("icon.")));
+}
+
+#[test]
+fn manifest_sprite_sheets_are_cut_into_frames() {
+    let ctx = egui::Context::default();
+    let sheet = |frame: u32, frames: Option<u32>| {
+        let json = json!({
+            "sprite_sheets": [{
+                "id": "Hands", "path": "image/hand.png",
+                "frame_width": frame, "frame_height": frame, "frames": frames
+            }]
+        });
+        AssetManifest::from_json(&json.to_string(), "manifest.json").unwrap()
+    };
+
+    // hand.png is 80x80, four frames of 40x40
+    let assets = MyAssets::load_manifest(&ctx, &sheet(40, None)).unwrap();
+    let hands = assets.sprite_sheet("Hands").unwrap();
+    assert_eq!(hands.frames, 4);
+    let uv = hands.uv(3);
+    assert_eq!(
+        (uv.min, uv.max),
+        (egui::pos2(0.5, 0.5), egui::pos2(1.0, 1.0))
+    );
+    assert_eq!(hands.uv(4), hands.uv(0));
+
+    for manifest in [sheet(30, None), sheet(40, Some(5))] {
+        match MyAssets::load_manifest(&ctx, &manifest) {
+            Err(Error::Asset { path, .. }) => assert_eq!(path, "image/hand.png"),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("frames that do not fit were loaded"),
+        }
+    }
+
+    let sound = r#"{ "sounds": [{ "id": "Catch", "path": "sound/catch.png" }] }"#;
+    let manifest = AssetManifest::from_json(sound, "manifest.json").unwrap();
+    assert_eq!(
+        manifest.validate(&ASSETS),
+        [
+            "sound Catch: sound/catch.png is not in the assets",
+            "sound Catch: sound/catch.png is not a ogg, wav, mp3 or flac file",
+        ]
+    );
+}
+
+#[test]
+fn skin_packs_load_from_directories_and_archives() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/skins");
+    let skins = discover_skins(&fixtures);
+    let names: Vec<&str> = skins.iter().map(|skin| skin.name.as_str()).collect();
+    assert_eq!(names, ["Autumn", "Night"]);
+
+    // a zip archive of the pack directory
+    let autumn = &skins[0];
+    assert_eq!(autumn.id, "autumn");
+    assert_eq!(autumn.look.fly_size, DEFAULT_FLY_SIZE);
+    assert_eq!(
+        autumn.look.background,
+        egui::Color32::from_rgb(0xff, 0xc0, 0x80)
+    );
+    assert_eq!(autumn.read("leaf.png").unwrap().len(), 75);
+
+    let night = &skins[1];
+    assert_eq!(
+        night.look,
+        Look {
+            fly_size: 72.0,
+            hand_size: 40.0,
+            text: Some(egui::Color32::from_rgb(0xf0, 0xe6, 0x8c)),
+            hand: egui::Color32::from_rgb(0xff, 0xcc, 0x00),
+            ..Look::default()
+        }
+    );
+    assert!(night.read("../autumn.zip").is_err());
+
+    let ctx = egui::Context::default();
+    let mut assets = MyAssets::load_from_json(&ctx).unwrap();
+    assets.apply_skin(&ctx, night).unwrap();
+    assert_eq!(assets.get("Beach").unwrap().size(), [8, 6]);
+    assets.apply_skin(&ctx, autumn).unwrap();
+    assert_eq!(assets.get("Rustacean").unwrap().size(), [4, 4]);
+    assert_eq!(assets.get("Beach").unwrap().size(), [8, 6]);
+}
+
+#[test]
+fn broken_skin_packs_are_refused() {
+    let dir = std::env::temp_dir().join(format!("flyrust-test-{}-skins", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let pack = |name: &str, skin: serde_json::Value| {
+        let path = dir.join(name);
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("skin.json"), skin.to_string()).unwrap();
+        std::fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/skins/night/moon-beach.png"),
+            path.join("moon.png"),
+        )
+        .unwrap();
+        path
+    };
+    let broken = [
+        pack(
+            "colour",
+            json!({ "name": "Colour", "colors": { "fly": "red" } }),
+        ),
+        pack("size", json!({ "name": "Size", "sizes": { "hand": -4 } })),
+        // a fly bigger than the margin of the beach
+        pack("giant", json!({ "name": "Giant", "sizes": { "fly": 120 } })),
+        pack(
+            "file",
+            json!({ "name": "File", "textures": { "Beach": "sun.png" } }),
+        ),
+        pack("key", json!({ "name": "Key", "sounds": {} })),
+    ];
+    for path in &broken {
+        match Skin::load(path) {
+            Err(Error::Asset { path: p, .. }) => assert_eq!(Path::new(&p), path),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("{} was loaded", path.display()),
+        }
+    }
+    assert!(discover_skins(&dir).is_empty());
+
+    // a texture the asset manifest does not have
+    let moon = Skin::load(&pack(
+        "moon",
+        json!({ "name": "Moon", "textures": { "Moon": "moon.png" } }),
+    ))
+    .unwrap();
+    let ctx = egui::Context::default();
+    let mut assets = MyAssets::load_from_json(&ctx).unwrap();
+    assert!(matches!(
+        assets.apply_skin(&ctx, &moon),
+        Err(Error::Asset { .. })
+    ));
+    assert_eq!(assets.get("Beach").unwrap().size(), [800, 600]);
+}
+
+#[test]
+fn settings_remember_the_skin_and_skin_fonts_come_first() {
+    let path =
+        std::env::temp_dir().join(format!("flyrust-test-{}-settings.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(Settings::load(&path), Settings::default());
+    let settings = Settings {
+        skin: Some("night".to_string()),
+    };
+    settings.save(&path).unwrap();
+    assert_eq!(Settings::load(&path), settings);
+    std::fs::write(&path, "{ not json").unwrap();
+    assert_eq!(Settings::load(&path), Settings::default());
+
+    let mut fonts = FontLibrary::load().unwrap();
+    let english = fonts.chain("en");
+    let hack = egui::FontDefinitions::default().font_data["Hack"]
+        .font
+        .to_vec();
+    fonts
+        .set_skin_fonts(&[("night: mono.ttf".to_string(), hack)])
+        .unwrap();
+    assert_eq!(fonts.chain("en")[0], "skin:night: mono.ttf");
+    assert_eq!(fonts.chain("en")[1..], english);
+    assert!(matches!(
+        fonts.set_skin_fonts(&[("night: bad.ttf".to_string(), vec![0; 16])]),
+        Err(Error::Asset { .. })
+    ));
+    assert_eq!(fonts.chain("en").len(), english.len() + 1);
+    fonts.set_skin_fonts(&[]).unwrap();
+    assert_eq!(fonts.chain("en"), english);
+}
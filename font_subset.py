@@ -0,0 +1,239 @@
+#!/usr/bin/env python3
+# Subset GNU Unifont to the CJK characters of the translations
+#
+# Builds assets/fonts/unifont-cjk.ttf, the bundled CJK face of
+# assets/fonts/fonts.json, from a Unifont .hex file: the characters of the
+# locale files, the CJK punctuation and the fullwidth forms. Run it again
+# after a translation adds characters, `flyrust check-fonts` lists them.
+#
+# Usage:
+#   ./font_subset.py <unifont.hex> [<out.ttf>]
+#
+# Example:
+#   ./font_subset.py unifont-13.0.06.hex assets/fonts/unifont-cjk.ttf
+import os
+import struct
+import sys
+
+I18N_DIR = "Contents/Resources/assets/i18n"
+OUT = "assets/fonts/unifont-cjk.ttf"
+FAMILY = "Unifont CJK Subset"
+VERSION = "13.0.06"
+COPYRIGHT = "Copyright (C) 1998-2020 Roman Czyborra, Paul Hardy, et al."
+
+UPEM = 1024
+PIXEL = 64  # 16 pixels to the em
+ASCENT = 14 * PIXEL
+DESCENT = 2 * PIXEL
+
+# CJK punctuation and fullwidth forms
+RANGES = [(0x3000, 0x303F), (0xFF00, 0xFFEF)]
+
+
+def is_cjk(code):
+    return 0x2E80 <= code <= 0x9FFF or 0xF900 <= code <= 0xFAFF or 0xFF00 <= code <= 0xFFEF
+
+
+def wanted_chars():
+    codes = set()
+    for low, high in RANGES:
+        codes.update(range(low, high + 1))
+    for name in sorted(os.listdir(I18N_DIR)):
+        with open(os.path.join(I18N_DIR, name), encoding="utf-8") as f:
+            codes.update(ord(c) for c in f.read() if is_cjk(ord(c)))
+    return codes
+
+
+def read_hex(path, codes):
+    glyphs = {}
+    with open(path) as f:
+        for line in f:
+            code, bits = line.strip().split(":")
+            code = int(code, 16)
+            if code in codes:
+                glyphs[code] = bits
+    return glyphs
+
+
+# rectangles of the set pixels, runs of a row merged with the same runs below
+def rectangles(bits):
+    width = len(bits) // 4
+    digits = width // 4
+    rows = [int(bits[i : i + digits], 16) for i in range(0, len(bits), digits)]
+    open_runs = {}
+    rects = []
+    for y, row in enumerate(rows + [0]):
+        runs = set()
+        x = 0
+        while x < width:
+            if row >> (width - 1 - x) & 1:
+                start = x
+                while x < width and row >> (width - 1 - x) & 1:
+                    x += 1
+                runs.add((start, x))
+            x += 1
+        for run in list(open_runs):
+            if run not in runs:
+                rects.append((run[0], open_runs.pop(run), run[1], y))
+        for run in runs:
+            open_runs.setdefault(run, y)
+    return width, sorted(rects, key=lambda r: (r[1], r[0]))
+
+
+def glyph_data(rects):
+    if not rects:
+        return b"", (0, 0, 0, 0), 0, 0
+    contours = []
+    for x0, top, x1, bottom in rects:
+        left, right = x0 * PIXEL, x1 * PIXEL
+        low, high = ASCENT - bottom * PIXEL, ASCENT - top * PIXEL
+        # clockwise
+        contours.append([(left, low), (left, high), (right, high), (right, low)])
+    points = [p for c in contours for p in c]
+    xs = [p[0] for p in points]
+    ys = [p[1] for p in points]
+    box = (min(xs), min(ys), max(xs), max(ys))
+    out = struct.pack(">hhhhh", len(contours), *box)
+    end = -1
+    for c in contours:
+        end += len(c)
+        out += struct.pack(">H", end)
+    out += struct.pack(">H", 0)
+    flags, xdata, ydata = [], b"", b""
+    px = py = 0
+    for x, y in points:
+        dx, dy = x - px, y - py
+        px, py = x, y
+        flag = 0x01
+        if dx == 0:
+            flag |= 0x10
+        elif -256 < dx < 256:
+            flag |= 0x02 | (0x10 if dx > 0 else 0)
+            xdata += struct.pack(">B", abs(dx))
+        else:
+            xdata += struct.pack(">h", dx)
+        if dy == 0:
+            flag |= 0x20
+        elif -256 < dy < 256:
+            flag |= 0x04 | (0x20 if dy > 0 else 0)
+            ydata += struct.pack(">B", abs(dy))
+        else:
+            ydata += struct.pack(">h", dy)
+        flags.append(flag)
+    out += bytes(flags) + xdata + ydata
+    return out, box, len(points), len(contours)
+
+
+def cmap_table(codes):
+    segments = []
+    for gid, code in enumerate(codes, start=1):
+        if segments and segments[-1][1] == code - 1 and segments[-1][2] == gid - (code - segments[-1][0]):
+            segments[-1][1] = code
+        else:
+            segments.append([code, code, gid])
+    segments.append([0xFFFF, 0xFFFF, 0])
+    count = len(segments)
+    search = 2 ** (count.bit_length() - 1)
+    ends = b"".join(struct.pack(">H", s[1]) for s in segments)
+    starts = b"".join(struct.pack(">H", s[0]) for s in segments)
+    deltas = b"".join(struct.pack(">H", (s[2] - s[0]) % 0x10000 if s[0] != 0xFFFF else 1) for s in segments)
+    offsets = b"\0\0" * count
+    body = struct.pack(">HHH", search * 2, search.bit_length() - 1, count * 2 - search * 2)
+    body += ends + b"\0\0" + starts + deltas + offsets
+    sub = struct.pack(">HHHH", 4, 8 + len(body), 0, count * 2) + body
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + sub
+
+
+def name_table():
+    names = {
+        0: f"{COPYRIGHT} Subset of GNU Unifont {VERSION}.",
+        1: FAMILY,
+        2: "Regular",
+        3: f"{FAMILY} {VERSION}",
+        4: FAMILY,
+        5: f"Version {VERSION}",
+        6: FAMILY.replace(" ", ""),
+        13: "This Font Software is licensed under the SIL Open Font License, Version 1.1.",
+        14: "https://openfontlicense.org",
+    }
+    records, strings = b"", b""
+    for name_id, text in sorted(names.items()):
+        data = text.encode("utf-16-be")
+        records += struct.pack(">HHHHHH", 3, 1, 0x0409, name_id, len(data), len(strings))
+        strings += data
+    return struct.pack(">HHH", 0, len(names), 6 + len(records)) + records + strings
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(f">{len(data) // 4}I", data)) & 0xFFFFFFFF
+
+
+def build(glyphs):
+    codes = sorted(glyphs)
+    glyf, loca, hmtx = b"", [0], struct.pack(">Hh", UPEM // 2, 0)
+    max_points = max_contours = 0
+    bounds = [0, -DESCENT, UPEM, ASCENT]
+    loca.append(0)  # empty .notdef
+    for code in codes:
+        width, rects = rectangles(glyphs[code])
+        data, box, points, contours = glyph_data(rects)
+        data += b"\0" * (-len(data) % 4)
+        glyf += data
+        loca.append(len(glyf))
+        hmtx += struct.pack(">Hh", width * PIXEL, box[0])
+        max_points = max(max_points, points)
+        max_contours = max(max_contours, contours)
+        if data:
+            bounds = [min(bounds[0], box[0]), min(bounds[1], box[1]), max(bounds[2], box[2]), max(bounds[3], box[3])]
+    count = len(codes) + 1
+
+    tables = {
+        b"cmap": cmap_table(codes),
+        b"glyf": glyf,
+        b"head": struct.pack(
+            ">IIIIHHqqhhhhHHhhh", 0x00010000, 0x000D0000, 0, 0x5F0F3CF5, 0x000B, UPEM, 0, 0, *bounds, 0, 8, 2, 1, 0
+        ),
+        b"hhea": struct.pack(
+            ">IhhhHhhhhhhhhhhhH", 0x00010000, ASCENT, -DESCENT, 0, UPEM, 0, 0, UPEM, 1, 0, 0, 0, 0, 0, 0, 0, count
+        ),
+        b"hmtx": hmtx,
+        b"loca": b"".join(struct.pack(">I", o) for o in loca),
+        b"maxp": struct.pack(">IHHHHHHHHHHHHHH", 0x00010000, count, max_points, max_contours, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0),
+        b"name": name_table(),
+        b"OS/2": struct.pack(
+            ">HhHHHhhhhhhhhhhh10sIIII4sHHHhhhHHIIhhHHH",
+            4, UPEM, 400, 5, 0, 512, 512, 0, 128, 512, 512, 0, 384, PIXEL, 5 * PIXEL, 0, bytes(10),
+            0, 0x08010000, 0x10, 0, b"UNIF", 0x40, min(codes[0], 0xFFFF), min(codes[-1], 0xFFFF),
+            ASCENT, -DESCENT, 0, ASCENT, DESCENT, 0x00140000, 0, 8 * PIXEL, 10 * PIXEL, 0, 0x20, 0,
+        ),
+        b"post": struct.pack(">IIhhIIIII", 0x00030000, 0, -PIXEL, PIXEL, 0, 0, 0, 0, 0),
+    }
+
+    tags = sorted(tables)
+    search = 2 ** (len(tags).bit_length() - 1)
+    font = struct.pack(">IHHHH", 0x00010000, len(tags), search * 16, search.bit_length() - 1, len(tags) * 16 - search * 16)
+    offset = len(font) + 16 * len(tags)
+    directory, body = b"", b""
+    for tag in tags:
+        data = tables[tag]
+        directory += struct.pack(">4sIII", tag, checksum(data), offset + len(body), len(data))
+        body += data + b"\0" * (-len(data) % 4)
+    font += directory + body
+    head = offset + sum(len(tables[t]) + (-len(tables[t]) % 4) for t in tags[: tags.index(b"head")])
+    adjustment = (0xB1B0AFBA - checksum(font)) & 0xFFFFFFFF
+    return font[: head + 8] + struct.pack(">I", adjustment) + font[head + 12 :]
+
+
+def main():
+    if len(sys.argv) < 2:
+        sys.exit(f"Usage: {sys.argv[0]} <unifont.hex> [<out.ttf>]")
+    out = sys.argv[2] if len(sys.argv) > 2 else OUT
+    glyphs = read_hex(sys.argv[1], wanted_chars())
+    with open(out, "wb") as f:
+        f.write(build(glyphs))
+    print(f"{out}: {len(glyphs)} glyphs")
+
+
+if __name__ == "__main__":
+    main()